@@ -1,29 +1,255 @@
+use context::InContext;
+use error::*;
+use num_bigint::BigUint;
 use std::ffi::CStr;
 use z3_sys;
 use Context;
+use DeclKind;
+use FuncDecl;
+use Sort;
 
-pub struct Ast {
+/// The kind of a Z3 term.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AstKind {
+    Numeral,
+    /// An application of a function, including constants and operators.
+    App,
+    /// A variable bound by a quantifier.
+    Var,
+    Quantifier,
+    Sort,
+    FuncDecl,
+    Unknown,
+}
+
+/// A reference-counted handle to a Z3 term, bound to the lifetime of the
+/// `Context` that created it. Passing it to another context is an
+/// `ErrorKind::ContextMismatch`.
+pub struct Ast<'c> {
     pub(crate) ast: z3_sys::Z3_ast,
+    pub(crate) context: &'c Context,
+}
+
+impl<'c> InContext for Ast<'c> {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        Some(self.context.context)
+    }
 }
 
-impl Ast {
-    pub fn to_string(&self, context: &Context) -> Option<String> {
-        let s = unsafe { z3_sys::Z3_ast_to_string(context.context, self.ast) };
+impl<'c> Ast<'c> {
+    /// Wrap a raw `Z3_ast`, taking a reference to it.
+    pub(crate) fn new(context: &'c Context, ast: z3_sys::Z3_ast) -> Ast<'c> {
+        unsafe {
+            z3_sys::Z3_inc_ref(context.context, ast);
+        }
+        Ast { ast, context }
+    }
+
+    /// Wrap a raw `Z3_ast` returned by a call which may have failed.
+    pub(crate) fn try_new(context: &'c Context, ast: z3_sys::Z3_ast) -> Result<Ast<'c>> {
+        context.check_error()?;
+        Ok(Ast::new(context, ast))
+    }
+
+    /// Take every term out of a `Z3_ast_vector`, releasing the vector.
+    pub(crate) fn from_ast_vector(
+        context: &'c Context,
+        vector: z3_sys::Z3_ast_vector,
+    ) -> Vec<Ast<'c>> {
+        unsafe {
+            z3_sys::Z3_ast_vector_inc_ref(context.context, vector);
+            let asts = (0..z3_sys::Z3_ast_vector_size(context.context, vector))
+                .map(|i| {
+                    Ast::new(
+                        context,
+                        z3_sys::Z3_ast_vector_get(context.context, vector, i),
+                    )
+                })
+                .collect();
+            z3_sys::Z3_ast_vector_dec_ref(context.context, vector);
+            asts
+        }
+    }
+
+    pub fn kind(&self) -> AstKind {
+        match unsafe { z3_sys::Z3_get_ast_kind(self.context.context, self.ast) } {
+            z3_sys::AstKind::Numeral => AstKind::Numeral,
+            z3_sys::AstKind::App => AstKind::App,
+            z3_sys::AstKind::Var => AstKind::Var,
+            z3_sys::AstKind::Quantifier => AstKind::Quantifier,
+            z3_sys::AstKind::Sort => AstKind::Sort,
+            z3_sys::AstKind::FuncDecl => AstKind::FuncDecl,
+            z3_sys::AstKind::Unknown => AstKind::Unknown,
+        }
+    }
+
+    pub fn sort(&self) -> Sort<'c> {
+        Sort::new(self.context, unsafe {
+            z3_sys::Z3_get_sort(self.context.context, self.ast)
+        })
+    }
+
+    /// True if this term is an application. Numerals are applications too.
+    pub fn is_app(&self) -> bool {
+        unsafe { z3_sys::Z3_is_app(self.context.context, self.ast) }
+    }
+
+    pub fn is_numeral(&self) -> bool {
+        unsafe { z3_sys::Z3_is_numeral_ast(self.context.context, self.ast) }
+    }
+
+    /// The number of arguments of this application, or 0 if this term is
+    /// not an application.
+    pub fn num_args(&self) -> usize {
+        if !self.is_app() {
+            return 0;
+        }
+        unsafe {
+            let app = z3_sys::Z3_to_app(self.context.context, self.ast);
+            z3_sys::Z3_get_app_num_args(self.context.context, app) as usize
+        }
+    }
+
+    /// The `i`th argument of this application.
+    pub fn arg(&self, i: usize) -> Option<Ast<'c>> {
+        if i >= self.num_args() {
+            return None;
+        }
+        Some(Ast::new(self.context, unsafe {
+            let app = z3_sys::Z3_to_app(self.context.context, self.ast);
+            z3_sys::Z3_get_app_arg(self.context.context, app, i as u32)
+        }))
+    }
+
+    /// The arguments of this application.
+    pub fn args(&self) -> Vec<Ast<'c>> {
+        (0..self.num_args()).filter_map(|i| self.arg(i)).collect()
+    }
+
+    /// The function this term applies, if it is an application.
+    pub fn decl(&self) -> Option<FuncDecl<'c>> {
+        if !self.is_app() {
+            return None;
+        }
+        Some(FuncDecl::new(self.context, unsafe {
+            let app = z3_sys::Z3_to_app(self.context.context, self.ast);
+            z3_sys::Z3_get_app_decl(self.context.context, app)
+        }))
+    }
+
+    /// The operation this term applies, if it is an application.
+    pub fn decl_kind(&self) -> Option<DeclKind> {
+        self.decl().map(|decl| decl.kind())
+    }
+
+    pub fn to_string(&self) -> Option<String> {
+        let s = unsafe { z3_sys::Z3_ast_to_string(self.context.context, self.ast) };
         if s.is_null() {
             None
         } else {
-            let cs = unsafe { CStr::from_ptr(s as *mut i8) };
+            let cs = unsafe { CStr::from_ptr(s) };
             cs.to_str().ok().map(|s| s.to_string())
         }
     }
 
-    pub fn get_numeral_decimal_string(&self, context: &Context) -> Option<String> {
-        let s = unsafe { z3_sys::Z3_get_numeral_decimal_string(context.context, self.ast, 10000) };
+    /// The value of this numeral, if it is a numeral which fits in a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        if !self.is_numeral() {
+            return None;
+        }
+        let mut u: u64 = 0;
+        if unsafe { z3_sys::Z3_get_numeral_uint64(self.context.context, self.ast, &mut u) } {
+            Some(u)
+        } else {
+            None
+        }
+    }
+
+    /// The value of this numeral as an unsigned integer.
+    pub fn as_biguint(&self) -> Option<BigUint> {
+        if let Some(u) = self.as_u64() {
+            return Some(BigUint::from(u));
+        }
+        if !self.is_numeral() {
+            return None;
+        }
+        let s = unsafe { z3_sys::Z3_get_numeral_string(self.context.context, self.ast) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            BigUint::parse_bytes(cs.to_bytes(), 10)
+        }
+    }
+
+    pub fn get_numeral_decimal_string(&self) -> Option<String> {
+        let s =
+            unsafe { z3_sys::Z3_get_numeral_decimal_string(self.context.context, self.ast, 10000) };
         if s.is_null() {
             None
         } else {
-            let cs = unsafe { CStr::from_ptr(s as *mut i8) };
+            let cs = unsafe { CStr::from_ptr(s) };
             cs.to_str().ok().map(|s| s.to_string())
         }
     }
 }
+
+impl<'c> Clone for Ast<'c> {
+    fn clone(&self) -> Ast<'c> {
+        Ast::new(self.context, self.ast)
+    }
+}
+
+impl<'c> Drop for Ast<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_dec_ref(self.context.context, self.ast) }
+    }
+}
+
+#[test]
+fn test_introspection() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let sort8 = context.mk_bv_sort(8);
+    let sort16 = context.mk_bv_sort(16);
+    let a = context.mk_var("a", &sort8)?;
+    let b = context.mk_var("b", &sort16)?;
+
+    let term = context.extract(11, 4, &context.bvadd(&context.zero_ext(8, &a), &b));
+    assert_eq!(term.kind(), AstKind::App);
+    assert_eq!(term.sort().bv_size(), Some(8));
+    assert_eq!(term.decl_kind(), Some(DeclKind::Extract));
+    assert_eq!(term.decl().unwrap().int_parameters(), vec![11, 4]);
+    assert_eq!(term.num_args(), 1);
+    assert!(term.arg(1).is_none());
+
+    let sum = term.arg(0).unwrap();
+    assert_eq!(sum.decl_kind(), Some(DeclKind::Bvadd));
+    assert_eq!(sum.num_args(), 2);
+    let zero_ext = sum.arg(0).unwrap();
+    assert_eq!(zero_ext.decl_kind(), Some(DeclKind::ZeroExt));
+    assert_eq!(zero_ext.decl().unwrap().int_parameters(), vec![8]);
+    let a = zero_ext.arg(0).unwrap();
+    assert_eq!(a.decl_kind(), Some(DeclKind::Uninterpreted));
+    assert_eq!(a.decl().unwrap().name(), "a");
+    assert!(!a.is_numeral());
+    assert_eq!(a.as_u64(), None);
+
+    let numeral =
+        context.mk_numeral_str("123456789012345678901234567890", &context.mk_bv_sort(128))?;
+    assert!(numeral.is_numeral());
+    assert_eq!(numeral.kind(), AstKind::Numeral);
+    assert_eq!(numeral.decl_kind(), Some(DeclKind::BvNumeral));
+    assert_eq!(numeral.as_u64(), None);
+    assert_eq!(
+        numeral.as_biguint().unwrap().to_string(),
+        "123456789012345678901234567890"
+    );
+    assert_eq!(context.mk_numeral(42, &sort8)?.as_u64(), Some(42));
+
+    assert_eq!(context.mk_true().sort().bv_size(), None);
+    assert_eq!(context.mk_true().decl_kind(), Some(DeclKind::True));
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
-use std::ffi::CString;
+use error::*;
+use std::ffi::{CStr, CString};
+use std::ptr::null;
 use z3_sys;
 
+/// Parameters for creating a `Context`.
+///
+/// Z3 prints a warning, rather than failing, for a parameter it does not
+/// know.
 pub struct Config {
     pub(crate) config: z3_sys::Z3_config,
 }
@@ -12,13 +18,92 @@ impl Config {
         }
     }
 
-    pub fn enable_model(self) -> Self {
-        let model = CString::new("model").unwrap();
-        let true_ = CString::new("true").unwrap();
+    /// Set the context parameter `key` to `value`.
+    pub fn set(self, key: &str, value: &str) -> Result<Self> {
+        let key = CString::new(key)?;
+        let value = CString::new(value)?;
         unsafe {
-            z3_sys::Z3_set_param_value(self.config, model.as_ptr(), true_.as_ptr());
+            z3_sys::Z3_set_param_value(self.config, key.as_ptr(), value.as_ptr());
         }
-        self
+        Ok(self)
+    }
+
+    /// Set a parameter whose key and value are known to hold no NUL bytes.
+    fn set_known(self, key: &str, value: &str) -> Self {
+        self.set(key, value).expect("parameter holds a NUL byte")
+    }
+
+    fn set_bool(self, key: &str, value: bool) -> Self {
+        self.set_known(key, if value { "true" } else { "false" })
+    }
+
+    pub fn enable_model(self) -> Self {
+        self.set_bool("model", true)
+    }
+
+    /// Enable proof generation, for `Solver::proof`.
+    pub fn proof(self, enable: bool) -> Self {
+        self.set_bool("proof", enable)
+    }
+
+    /// Enable unsat core generation.
+    pub fn unsat_core(self, enable: bool) -> Self {
+        self.set_bool("unsat_core", enable)
+    }
+
+    /// Give up on checks after `milliseconds`.
+    pub fn timeout(self, milliseconds: u32) -> Self {
+        self.set_known("timeout", &milliseconds.to_string())
+    }
+
+    /// Type check terms as they are created.
+    pub fn well_sorted_check(self, enable: bool) -> Self {
+        self.set_bool("well_sorted_check", enable)
+    }
+
+    /// Let Z3 pick solver settings from the problem.
+    pub fn auto_config(self, enable: bool) -> Self {
+        self.set_bool("auto_config", enable)
+    }
+
+    /// Enable tracing, if Z3 was built with tracing support.
+    pub fn trace(self, enable: bool) -> Self {
+        self.set_bool("trace", enable)
+    }
+
+    /// The file tracing writes to.
+    pub fn trace_file_name(self, trace_file_name: &str) -> Result<Self> {
+        self.set("trace_file_name", trace_file_name)
+    }
+
+    /// Set the global parameter `key` to `value`. Global parameters apply to
+    /// every context, solver and tactic created after they are set.
+    pub fn set_global(key: &str, value: &str) -> Result<()> {
+        let key = CString::new(key)?;
+        let value = CString::new(value)?;
+        unsafe { z3_sys::Z3_global_param_set(key.as_ptr(), value.as_ptr()) }
+        Ok(())
+    }
+
+    /// The value of the global parameter `key`, or `None` if there is no
+    /// such parameter.
+    pub fn get_global(key: &str) -> Result<Option<String>> {
+        let key = CString::new(key)?;
+        let mut value = null();
+        if unsafe { z3_sys::Z3_global_param_get(key.as_ptr(), &mut value) } && !value.is_null() {
+            Ok(Some(
+                unsafe { CStr::from_ptr(value) }
+                    .to_string_lossy()
+                    .into_owned(),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Restore every global parameter to its default.
+    pub fn reset_global() {
+        unsafe { z3_sys::Z3_global_param_reset_all() }
     }
 }
 
@@ -1,202 +1,774 @@
-use std::ffi::CString;
+use error::*;
+use num_bigint::BigUint;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 use z3_sys;
 use Ast;
 use Config;
+use FuncDecl;
+use Params;
 use Sort;
 
+/// Generates a builder which panics on a Z3 error, and a `try_` variant of
+/// that builder which returns the error instead.
+macro_rules! builder {
+    ($(#[$attr:meta])* $name:ident, $try_name:ident, $z3_fn:ident,
+     ($($arg:ident: $ty:ty),*), ($($z3_arg:expr),*)) => {
+        $(#[$attr])*
+        pub fn $name<'c>(&'c self, $($arg: $ty),*) -> Ast<'c> {
+            self.$try_name($($arg),*).unwrap()
+        }
+
+        $(#[$attr])*
+        pub fn $try_name<'c>(&'c self, $($arg: $ty),*) -> Result<Ast<'c>> {
+            $(self.check_context(&$arg)?;)*
+            Ast::try_new(self, unsafe { z3_sys::$z3_fn(self.context, $($z3_arg),*) })
+        }
+    };
+}
+
+macro_rules! unary_builder {
+    ($(#[$attr:meta])* $name:ident, $try_name:ident, $z3_fn:ident) => {
+        builder!($(#[$attr])* $name, $try_name, $z3_fn, (t1: &Ast<'c>), (t1.ast));
+    };
+}
+
+macro_rules! binary_builder {
+    ($(#[$attr:meta])* $name:ident, $try_name:ident, $z3_fn:ident) => {
+        builder!(
+            $(#[$attr])* $name, $try_name, $z3_fn,
+            (lhs: &Ast<'c>, rhs: &Ast<'c>),
+            (lhs.ast, rhs.ast)
+        );
+    };
+}
+
+macro_rules! slice_builder {
+    ($(#[$attr:meta])* $name:ident, $try_name:ident, $z3_fn:ident) => {
+        $(#[$attr])*
+        pub fn $name<'c>(&'c self, args: &[&Ast<'c>]) -> Ast<'c> {
+            self.$try_name(args).unwrap()
+        }
+
+        $(#[$attr])*
+        pub fn $try_name<'c>(&'c self, args: &[&Ast<'c>]) -> Result<Ast<'c>> {
+            self.check_contexts(args)?;
+            let args: Vec<z3_sys::Z3_ast> = args.iter().map(|arg| arg.ast).collect();
+            Ast::try_new(self, unsafe {
+                z3_sys::$z3_fn(self.context, args.len() as u32, args.as_ptr())
+            })
+        }
+    };
+}
+
+/// A value which may belong to a `Context`. Lifetimes cannot tell two
+/// contexts in the same scope apart, so calls taking Z3 objects check that
+/// they belong to the context they are used with.
+pub(crate) trait InContext {
+    /// The raw context this value belongs to, if any.
+    fn z3_context(&self) -> Option<z3_sys::Z3_context>;
+}
+
+impl<T: InContext + ?Sized> InContext for &T {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        (**self).z3_context()
+    }
+}
+
+impl InContext for u32 {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        None
+    }
+}
+
+impl InContext for bool {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        None
+    }
+}
+
+/// Z3 calls its error handler on every failed API call. Installing a handler
+/// which returns keeps Z3 from aborting the process, and leaves the error
+/// code on the context for `Context::check_error`.
+extern "C" fn error_handler(_: z3_sys::Z3_context, _: z3_sys::ErrorCode) {}
+
 pub struct Context {
     pub(crate) context: z3_sys::Z3_context,
 }
 
+/// Interrupts work on a `Context` from another thread.
+///
+/// A `check` interrupted through this handle returns `Check::Unknown`, with a
+/// `reason_unknown` of "canceled" ("interrupted" on older releases of Z3).
+pub struct InterruptHandle<'c> {
+    context: z3_sys::Z3_context,
+    _marker: PhantomData<&'c Context>,
+}
+
+impl<'c> InterruptHandle<'c> {
+    pub fn interrupt(&self) {
+        unsafe { z3_sys::Z3_interrupt(self.context) }
+    }
+}
+
+// Z3_interrupt is the one call Z3 allows on a context from another thread,
+// and the borrow keeps the context alive for the life of the handle.
+unsafe impl<'c> Send for InterruptHandle<'c> {}
+unsafe impl<'c> Sync for InterruptHandle<'c> {}
+
 impl Context {
     pub fn new(config: Config) -> Context {
-        Context {
-            context: unsafe { z3_sys::Z3_mk_context(config.config) },
+        let context = unsafe { z3_sys::Z3_mk_context_rc(config.config) };
+        unsafe {
+            z3_sys::Z3_set_error_handler(context, Some(error_handler));
         }
+        Context { context }
     }
 
-    pub fn bvadd(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvadd(self.context, lhs.ast, rhs.ast) },
+    /// A handle which can interrupt work on this context from another thread.
+    pub fn interrupt_handle<'c>(&'c self) -> InterruptHandle<'c> {
+        InterruptHandle {
+            context: self.context,
+            _marker: PhantomData,
         }
     }
 
-    pub fn bvand(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvand(self.context, lhs.ast, rhs.ast) },
+    /// Returns an `ErrorKind::ContextMismatch` if `value` belongs to another
+    /// context.
+    pub(crate) fn check_context<T: InContext + ?Sized>(&self, value: &T) -> Result<()> {
+        match value.z3_context() {
+            Some(context) if context != self.context => Err(ErrorKind::ContextMismatch.into()),
+            _ => Ok(()),
         }
     }
 
-    pub fn bvmul(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvmul(self.context, lhs.ast, rhs.ast) },
+    /// Returns an `ErrorKind::ContextMismatch` if any of `values` belongs to
+    /// another context.
+    pub(crate) fn check_contexts<T: InContext>(&self, values: &[T]) -> Result<()> {
+        for value in values {
+            self.check_context(value)?;
         }
+        Ok(())
     }
 
-    pub fn bvnot(&self, t1: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvnot(self.context, t1.ast) },
+    /// Returns an `ErrorKind::Z3` if the last call into Z3 on this context
+    /// failed.
+    pub(crate) fn check_error(&self) -> Result<()> {
+        let code = unsafe { z3_sys::Z3_get_error_code(self.context) };
+        if code == z3_sys::ErrorCode::OK {
+            return Ok(());
         }
+        let message = unsafe {
+            let s = z3_sys::Z3_get_error_msg(self.context, code);
+            if s.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(s).to_string_lossy().into_owned()
+            }
+        };
+        Err(ErrorKind::Z3(code, message).into())
     }
 
-    pub fn bvor(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvor(self.context, lhs.ast, rhs.ast) },
-        }
-    }
+    unary_builder!(
+        /// The default value of an array, such as the value of a constant
+        /// array.
+        array_default,
+        try_array_default,
+        Z3_mk_array_default
+    );
 
-    pub fn bvsdiv(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvsdiv(self.context, lhs.ast, rhs.ast) },
-        }
+    slice_builder!(
+        /// The conjunction of `args`. An empty conjunction is `true`.
+        and,
+        try_and,
+        Z3_mk_and
+    );
+
+    binary_builder!(bvadd, try_bvadd, Z3_mk_bvadd);
+
+    builder!(
+        /// True if `lhs + rhs` does not overflow, treating both as signed if
+        /// `is_signed` is set.
+        bvadd_no_overflow,
+        try_bvadd_no_overflow,
+        Z3_mk_bvadd_no_overflow,
+        (lhs: &Ast<'c>, rhs: &Ast<'c>, is_signed: bool),
+        (lhs.ast, rhs.ast, is_signed)
+    );
+
+    binary_builder!(
+        /// True if the signed `lhs + rhs` does not underflow.
+        bvadd_no_underflow,
+        try_bvadd_no_underflow,
+        Z3_mk_bvadd_no_underflow
+    );
+
+    binary_builder!(bvand, try_bvand, Z3_mk_bvand);
+
+    binary_builder!(
+        /// Arithmetic shift right.
+        bvashr,
+        try_bvashr,
+        Z3_mk_bvashr
+    );
+
+    binary_builder!(bvmul, try_bvmul, Z3_mk_bvmul);
+
+    builder!(
+        /// True if `lhs * rhs` does not overflow, treating both as signed if
+        /// `is_signed` is set.
+        bvmul_no_overflow,
+        try_bvmul_no_overflow,
+        Z3_mk_bvmul_no_overflow,
+        (lhs: &Ast<'c>, rhs: &Ast<'c>, is_signed: bool),
+        (lhs.ast, rhs.ast, is_signed)
+    );
+
+    binary_builder!(
+        /// True if the signed `lhs * rhs` does not underflow.
+        bvmul_no_underflow,
+        try_bvmul_no_underflow,
+        Z3_mk_bvmul_no_underflow
+    );
+
+    binary_builder!(bvnand, try_bvnand, Z3_mk_bvnand);
+
+    unary_builder!(
+        /// Two's complement negation.
+        bvneg,
+        try_bvneg,
+        Z3_mk_bvneg
+    );
+
+    unary_builder!(
+        /// True if negating the signed `t1` does not overflow.
+        bvneg_no_overflow,
+        try_bvneg_no_overflow,
+        Z3_mk_bvneg_no_overflow
+    );
+
+    binary_builder!(bvnor, try_bvnor, Z3_mk_bvnor);
+    unary_builder!(bvnot, try_bvnot, Z3_mk_bvnot);
+    binary_builder!(bvor, try_bvor, Z3_mk_bvor);
+
+    unary_builder!(
+        /// A 1-bit vector which is the conjunction of every bit in `t1`.
+        bvredand,
+        try_bvredand,
+        Z3_mk_bvredand
+    );
+
+    unary_builder!(
+        /// A 1-bit vector which is the disjunction of every bit in `t1`.
+        bvredor,
+        try_bvredor,
+        Z3_mk_bvredor
+    );
+
+    binary_builder!(bvsdiv, try_bvsdiv, Z3_mk_bvsdiv);
+
+    binary_builder!(
+        /// True if the signed `lhs / rhs` does not overflow.
+        bvsdiv_no_overflow,
+        try_bvsdiv_no_overflow,
+        Z3_mk_bvsdiv_no_overflow
+    );
+
+    binary_builder!(bvsge, try_bvsge, Z3_mk_bvsge);
+    binary_builder!(bvsgt, try_bvsgt, Z3_mk_bvsgt);
+    binary_builder!(bvshl, try_bvshl, Z3_mk_bvshl);
+    binary_builder!(bvlshr, try_bvlshr, Z3_mk_bvlshr);
+    binary_builder!(bvsle, try_bvsle, Z3_mk_bvsle);
+    binary_builder!(bvslt, try_bvslt, Z3_mk_bvslt);
+
+    binary_builder!(
+        /// Signed remainder, where the sign follows the divisor.
+        bvsmod,
+        try_bvsmod,
+        Z3_mk_bvsmod
+    );
+
+    binary_builder!(
+        /// Signed remainder, where the sign follows the dividend.
+        bvsrem,
+        try_bvsrem,
+        Z3_mk_bvsrem
+    );
+
+    binary_builder!(bvsub, try_bvsub, Z3_mk_bvsub);
+
+    binary_builder!(
+        /// True if the signed `lhs - rhs` does not overflow.
+        bvsub_no_overflow,
+        try_bvsub_no_overflow,
+        Z3_mk_bvsub_no_overflow
+    );
+
+    builder!(
+        /// True if `lhs - rhs` does not underflow, treating both as signed if
+        /// `is_signed` is set.
+        bvsub_no_underflow,
+        try_bvsub_no_underflow,
+        Z3_mk_bvsub_no_underflow,
+        (lhs: &Ast<'c>, rhs: &Ast<'c>, is_signed: bool),
+        (lhs.ast, rhs.ast, is_signed)
+    );
+
+    binary_builder!(bvudiv, try_bvudiv, Z3_mk_bvudiv);
+    binary_builder!(bvuge, try_bvuge, Z3_mk_bvuge);
+    binary_builder!(bvugt, try_bvugt, Z3_mk_bvugt);
+    binary_builder!(bvule, try_bvule, Z3_mk_bvule);
+    binary_builder!(bvult, try_bvult, Z3_mk_bvult);
+    binary_builder!(bvurem, try_bvurem, Z3_mk_bvurem);
+    binary_builder!(bvxnor, try_bvxnor, Z3_mk_bvxnor);
+    binary_builder!(bvxor, try_bvxor, Z3_mk_bvxor);
+    binary_builder!(concat, try_concat, Z3_mk_concat);
+
+    slice_builder!(
+        /// True if no two of `args` are equal.
+        distinct,
+        try_distinct,
+        Z3_mk_distinct
+    );
+
+    builder!(
+        /// An array indexed by `domain` which holds `value` at every index.
+        const_array,
+        try_const_array,
+        Z3_mk_const_array,
+        (domain: &Sort<'c>, value: &Ast<'c>),
+        (domain.sort, value.ast)
+    );
+
+    /// There exist values for the constants in `bound` for which `body`
+    /// holds.
+    pub fn exists<'c>(&'c self, bound: &[&Ast<'c>], body: &Ast<'c>) -> Ast<'c> {
+        self.try_exists(bound, body).unwrap()
     }
 
-    pub fn bvshl(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvshl(self.context, lhs.ast, rhs.ast) },
-        }
+    /// There exist values for the constants in `bound` for which `body`
+    /// holds.
+    pub fn try_exists<'c>(&'c self, bound: &[&Ast<'c>], body: &Ast<'c>) -> Result<Ast<'c>> {
+        self.quantifier(false, bound, &[], body)
     }
 
-    pub fn bvlshr(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvlshr(self.context, lhs.ast, rhs.ast) },
-        }
+    /// Like `exists`, with multi-patterns guiding instantiation.
+    pub fn exists_with_patterns<'c>(
+        &'c self,
+        bound: &[&Ast<'c>],
+        patterns: &[&[&Ast<'c>]],
+        body: &Ast<'c>,
+    ) -> Ast<'c> {
+        self.try_exists_with_patterns(bound, patterns, body)
+            .unwrap()
     }
 
-    pub fn bvsle(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvsle(self.context, lhs.ast, rhs.ast) },
-        }
+    /// Like `try_exists`, with multi-patterns guiding instantiation.
+    pub fn try_exists_with_patterns<'c>(
+        &'c self,
+        bound: &[&Ast<'c>],
+        patterns: &[&[&Ast<'c>]],
+        body: &Ast<'c>,
+    ) -> Result<Ast<'c>> {
+        self.quantifier(false, bound, patterns, body)
     }
 
-    pub fn bvslt(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvslt(self.context, lhs.ast, rhs.ast) },
-        }
+    builder!(
+        extract,
+        try_extract,
+        Z3_mk_extract,
+        (high: u32, low: u32, t1: &Ast<'c>),
+        (high, low, t1.ast)
+    );
+
+    binary_builder!(eq, try_eq, Z3_mk_eq);
+
+    /// `body` holds for all values of the constants in `bound`.
+    pub fn forall<'c>(&'c self, bound: &[&Ast<'c>], body: &Ast<'c>) -> Ast<'c> {
+        self.try_forall(bound, body).unwrap()
     }
 
-    pub fn bvsrem(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvsrem(self.context, lhs.ast, rhs.ast) },
-        }
+    /// `body` holds for all values of the constants in `bound`.
+    pub fn try_forall<'c>(&'c self, bound: &[&Ast<'c>], body: &Ast<'c>) -> Result<Ast<'c>> {
+        self.quantifier(true, bound, &[], body)
     }
 
-    pub fn bvsub(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvsub(self.context, lhs.ast, rhs.ast) },
-        }
+    /// Like `forall`, with multi-patterns guiding instantiation.
+    pub fn forall_with_patterns<'c>(
+        &'c self,
+        bound: &[&Ast<'c>],
+        patterns: &[&[&Ast<'c>]],
+        body: &Ast<'c>,
+    ) -> Ast<'c> {
+        self.try_forall_with_patterns(bound, patterns, body)
+            .unwrap()
     }
 
-    pub fn bvudiv(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvudiv(self.context, lhs.ast, rhs.ast) },
-        }
+    /// Like `try_forall`, with multi-patterns guiding instantiation.
+    pub fn try_forall_with_patterns<'c>(
+        &'c self,
+        bound: &[&Ast<'c>],
+        patterns: &[&[&Ast<'c>]],
+        body: &Ast<'c>,
+    ) -> Result<Ast<'c>> {
+        self.quantifier(true, bound, patterns, body)
     }
 
-    pub fn bvule(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvult(self.context, lhs.ast, rhs.ast) },
-        }
+    binary_builder!(iff, try_iff, Z3_mk_iff);
+    binary_builder!(implies, try_implies, Z3_mk_implies);
+
+    builder!(
+        ite,
+        try_ite,
+        Z3_mk_ite,
+        (condition: &Ast<'c>, then: &Ast<'c>, else_: &Ast<'c>),
+        (condition.ast, then.ast, else_.ast)
+    );
+
+    pub fn mk_bool_sort<'c>(&'c self) -> Sort<'c> {
+        Sort::new(self, unsafe { z3_sys::Z3_mk_bool_sort(self.context) })
     }
 
-    pub fn bvult(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvult(self.context, lhs.ast, rhs.ast) },
-        }
+    pub fn mk_bool_var<'c, S: Into<String>>(&'c self, name: S) -> Result<Ast<'c>> {
+        self.mk_var(name, &self.mk_bool_sort())
     }
 
-    pub fn bvurem(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvurem(self.context, lhs.ast, rhs.ast) },
-        }
+    /// A bitvector numeral of `bits.len()` bits, where `bits[0]` is the least
+    /// significant bit.
+    pub fn mk_bv_from_bits<'c>(&'c self, bits: &[bool]) -> Result<Ast<'c>> {
+        Ast::try_new(self, unsafe {
+            z3_sys::Z3_mk_bv_numeral(self.context, bits.len() as u32, bits.as_ptr())
+        })
     }
 
-    pub fn bvxor(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_bvxor(self.context, lhs.ast, rhs.ast) },
-        }
+    pub fn mk_bv_sort<'c>(&'c self, bits: usize) -> Sort<'c> {
+        self.try_mk_bv_sort(bits).unwrap()
     }
 
-    pub fn concat(&self, t1: &Ast, t2: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_concat(self.context, t1.ast, t2.ast) },
-        }
+    pub fn try_mk_bv_sort<'c>(&'c self, bits: usize) -> Result<Sort<'c>> {
+        Sort::try_new(self, unsafe {
+            z3_sys::Z3_mk_bv_sort(self.context, bits as u32)
+        })
     }
 
-    pub fn extract(&self, high: u32, low: u32, t1: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_extract(self.context, high, low, t1.ast) },
-        }
+    pub fn mk_false<'c>(&'c self) -> Ast<'c> {
+        Ast::new(self, unsafe { z3_sys::Z3_mk_false(self.context) })
     }
 
-    pub fn eq(&self, lhs: &Ast, rhs: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_eq(self.context, lhs.ast, rhs.ast) },
-        }
+    /// Declare an uninterpreted function from `domain` to `range`.
+    pub fn mk_func_decl<'c, S: Into<String>>(
+        &'c self,
+        name: S,
+        domain: &[&Sort<'c>],
+        range: &Sort<'c>,
+    ) -> Result<FuncDecl<'c>> {
+        self.check_contexts(domain)?;
+        self.check_context(range)?;
+        let name: CString = CString::new(name.into() as String)?;
+        let domain: Vec<z3_sys::Z3_sort> = domain.iter().map(|sort| sort.sort).collect();
+        FuncDecl::try_new(self, unsafe {
+            let symbol = z3_sys::Z3_mk_string_symbol(self.context, name.as_ptr());
+            z3_sys::Z3_mk_func_decl(
+                self.context,
+                symbol,
+                domain.len() as u32,
+                domain.as_ptr(),
+                range.sort,
+            )
+        })
     }
 
-    pub fn ite(&self, condition: &Ast, then: &Ast, else_: &Ast) -> Ast {
-        Ast {
-            ast: unsafe { z3_sys::Z3_mk_ite(self.context, condition.ast, then.ast, else_.ast) },
-        }
+    pub fn mk_numeral<'c>(&'c self, n: u64, sort: &Sort<'c>) -> Result<Ast<'c>> {
+        self.check_context(sort)?;
+        let num_string = CString::new(format!("{}", n))?;
+        Ast::try_new(self, unsafe {
+            z3_sys::Z3_mk_numeral(self.context, num_string.as_ptr(), sort.sort)
+        })
     }
 
-    pub fn mk_bv_sort(&self, bits: usize) -> Sort {
-        Sort {
-            sort: unsafe { z3_sys::Z3_mk_bv_sort(self.context, bits as u32) },
-        }
+    pub fn mk_numeral_biguint<'c>(&'c self, n: &BigUint, sort: &Sort<'c>) -> Result<Ast<'c>> {
+        self.mk_numeral_str(&n.to_str_radix(10), sort)
     }
 
-    pub fn mk_numeral(&self, n: u64, sort: &Sort) -> Result<Ast, ::std::ffi::NulError> {
-        let num_string = CString::new(format!("{}", n))?;
-        unsafe {
-            Ok(Ast {
-                ast: z3_sys::Z3_mk_numeral(self.context, num_string.as_ptr(), sort.sort),
-            })
-        }
+    /// A numeral from its decimal representation.
+    pub fn mk_numeral_str<'c>(&'c self, n: &str, sort: &Sort<'c>) -> Result<Ast<'c>> {
+        self.check_context(sort)?;
+        let num_string = CString::new(n)?;
+        Ast::try_new(self, unsafe {
+            z3_sys::Z3_mk_numeral(self.context, num_string.as_ptr(), sort.sort)
+        })
     }
 
-    pub fn mk_var<S: Into<String>>(
-        &self,
-        name: S,
-        sort: &Sort,
-    ) -> Result<Ast, ::std::ffi::NulError> {
+    pub fn mk_var<'c, S: Into<String>>(&'c self, name: S, sort: &Sort<'c>) -> Result<Ast<'c>> {
+        self.check_context(sort)?;
         let name: CString = CString::new(name.into() as String)?;
-        unsafe {
+        Ast::try_new(self, unsafe {
             let symbol = z3_sys::Z3_mk_string_symbol(self.context, name.as_ptr());
-            Ok(Ast {
-                ast: z3_sys::Z3_mk_const(self.context, symbol, sort.sort),
-            })
-        }
+            z3_sys::Z3_mk_const(self.context, symbol, sort.sort)
+        })
     }
 
-    pub fn not(&self, a: &Ast) -> Ast {
-        unsafe {
-            Ast {
-                ast: z3_sys::Z3_mk_not(self.context, a.ast),
-            }
-        }
+    pub(crate) fn mk_symbol(&self, name: &str) -> Result<z3_sys::Z3_symbol> {
+        let name = CString::new(name)?;
+        Ok(unsafe { z3_sys::Z3_mk_string_symbol(self.context, name.as_ptr()) })
     }
 
-    /// Sign extend `rhs` by `i` additional bytes. To sign-extend a 50-bit value
-    /// to a 60-bit value, `i` would be `10`.
-    pub fn sign_ext(&self, i: u32, rhs: &Ast) -> Ast {
-        unsafe {
-            Ast {
-                ast: z3_sys::Z3_mk_sign_ext(self.context, i, rhs.ast),
-            }
+    pub fn mk_true<'c>(&'c self) -> Ast<'c> {
+        Ast::new(self, unsafe { z3_sys::Z3_mk_true(self.context) })
+    }
+
+    unary_builder!(not, try_not, Z3_mk_not);
+
+    slice_builder!(
+        /// The disjunction of `args`. An empty disjunction is `false`.
+        or,
+        try_or,
+        Z3_mk_or
+    );
+
+    /// Parse an SMT-LIB2 benchmark, returning its assertions. `sorts` and
+    /// `decls` name sorts and functions the benchmark uses without declaring.
+    pub fn parse_smt2_string<'c>(
+        &'c self,
+        smt2: &str,
+        sorts: &[(&str, &Sort<'c>)],
+        decls: &[(&str, &FuncDecl<'c>)],
+    ) -> Result<Vec<Ast<'c>>> {
+        let smt2 = CString::new(smt2)?;
+        self.parse_smt2(sorts, decls, |sorts, decls| unsafe {
+            z3_sys::Z3_parse_smtlib2_string(
+                self.context,
+                smt2.as_ptr(),
+                sorts.0.len() as u32,
+                sorts.0.as_ptr(),
+                sorts.1.as_ptr(),
+                decls.0.len() as u32,
+                decls.0.as_ptr(),
+                decls.1.as_ptr(),
+            )
+        })
+    }
+
+    /// Parse the SMT-LIB2 benchmark in the file at `path`, as
+    /// `parse_smt2_string` does.
+    pub fn parse_smt2_file<'c>(
+        &'c self,
+        path: &str,
+        sorts: &[(&str, &Sort<'c>)],
+        decls: &[(&str, &FuncDecl<'c>)],
+    ) -> Result<Vec<Ast<'c>>> {
+        let path = CString::new(path)?;
+        self.parse_smt2(sorts, decls, |sorts, decls| unsafe {
+            z3_sys::Z3_parse_smtlib2_file(
+                self.context,
+                path.as_ptr(),
+                sorts.0.len() as u32,
+                sorts.0.as_ptr(),
+                sorts.1.as_ptr(),
+                decls.0.len() as u32,
+                decls.0.as_ptr(),
+                decls.1.as_ptr(),
+            )
+        })
+    }
+
+    fn parse_smt2<'c, F>(
+        &'c self,
+        sorts: &[(&str, &Sort<'c>)],
+        decls: &[(&str, &FuncDecl<'c>)],
+        parse: F,
+    ) -> Result<Vec<Ast<'c>>>
+    where
+        F: FnOnce(
+            (&[z3_sys::Z3_symbol], &[z3_sys::Z3_sort]),
+            (&[z3_sys::Z3_symbol], &[z3_sys::Z3_func_decl]),
+        ) -> z3_sys::Z3_ast,
+    {
+        for &(_, sort) in sorts {
+            self.check_context(sort)?;
         }
+        for &(_, decl) in decls {
+            self.check_context(decl)?;
+        }
+        let sort_names = sorts
+            .iter()
+            .map(|sort| self.mk_symbol(sort.0))
+            .collect::<Result<Vec<z3_sys::Z3_symbol>>>()?;
+        let sorts: Vec<z3_sys::Z3_sort> = sorts.iter().map(|sort| sort.1.sort).collect();
+        let decl_names = decls
+            .iter()
+            .map(|decl| self.mk_symbol(decl.0))
+            .collect::<Result<Vec<z3_sys::Z3_symbol>>>()?;
+        let decls: Vec<z3_sys::Z3_func_decl> = decls.iter().map(|decl| decl.1.func_decl).collect();
+
+        // z3-sys declares the parsers as returning a Z3_ast, but Z3 returns
+        // the assertions as a Z3_ast_vector.
+        let vector = parse((&sort_names, &sorts), (&decl_names, &decls)) as z3_sys::Z3_ast_vector;
+        self.check_error()?;
+        Ok(Ast::from_ast_vector(self, vector))
     }
 
-    /// Zero extend `rhs` by `i` additional bytes. To zero-extend a 50-bit value
-    /// to a 60-bit value, `i` would be `10`.
-    pub fn zero_ext(&self, i: u32, rhs: &Ast) -> Ast {
-        unsafe {
-            Ast {
-                ast: z3_sys::Z3_mk_zero_ext(self.context, i, rhs.ast),
-            }
+    fn quantifier<'c>(
+        &'c self,
+        is_forall: bool,
+        bound: &[&Ast<'c>],
+        patterns: &[&[&Ast<'c>]],
+        body: &Ast<'c>,
+    ) -> Result<Ast<'c>> {
+        self.check_contexts(bound)?;
+        for terms in patterns {
+            self.check_contexts(terms)?;
         }
+        self.check_context(body)?;
+        let bound: Vec<z3_sys::Z3_app> = bound
+            .iter()
+            .map(|constant| unsafe { z3_sys::Z3_to_app(self.context, constant.ast) })
+            .collect();
+
+        // Hold a reference to each pattern until the quantifier is built.
+        let mut pattern_asts = Vec::new();
+        let mut raw_patterns = Vec::new();
+        for terms in patterns {
+            let terms: Vec<z3_sys::Z3_ast> = terms.iter().map(|term| term.ast).collect();
+            let pattern =
+                unsafe { z3_sys::Z3_mk_pattern(self.context, terms.len() as u32, terms.as_ptr()) };
+            self.check_error()?;
+            pattern_asts.push(Ast::new(self, unsafe {
+                z3_sys::Z3_pattern_to_ast(self.context, pattern)
+            }));
+            raw_patterns.push(pattern);
+        }
+
+        Ast::try_new(self, unsafe {
+            z3_sys::Z3_mk_quantifier_const(
+                self.context,
+                is_forall,
+                0,
+                bound.len() as u32,
+                bound.as_ptr(),
+                raw_patterns.len() as u32,
+                raw_patterns.as_ptr(),
+                body.ast,
+            )
+        })
+    }
+
+    builder!(
+        /// Concatenate `i` copies of `t1`.
+        repeat,
+        try_repeat,
+        Z3_mk_repeat,
+        (i: u32, t1: &Ast<'c>),
+        (i, t1.ast)
+    );
+
+    builder!(
+        /// Rotate `t1` left by the constant `i` bits.
+        rotate_left,
+        try_rotate_left,
+        Z3_mk_rotate_left,
+        (i: u32, t1: &Ast<'c>),
+        (i, t1.ast)
+    );
+
+    binary_builder!(
+        /// Rotate `lhs` left by the number of bits given in `rhs`.
+        rotate_left_ext,
+        try_rotate_left_ext,
+        Z3_mk_ext_rotate_left
+    );
+
+    builder!(
+        /// Rotate `t1` right by the constant `i` bits.
+        rotate_right,
+        try_rotate_right,
+        Z3_mk_rotate_right,
+        (i: u32, t1: &Ast<'c>),
+        (i, t1.ast)
+    );
+
+    binary_builder!(
+        /// Rotate `lhs` right by the number of bits given in `rhs`.
+        rotate_right_ext,
+        try_rotate_right_ext,
+        Z3_mk_ext_rotate_right
+    );
+
+    builder!(
+        /// The value of `array` at `index`.
+        select,
+        try_select,
+        Z3_mk_select,
+        (array: &Ast<'c>, index: &Ast<'c>),
+        (array.ast, index.ast)
+    );
+
+    unary_builder!(
+        /// An equivalent, simplified form of `t1`.
+        simplify,
+        try_simplify,
+        Z3_simplify
+    );
+
+    /// An equivalent form of `t1`, simplified as configured by `params`.
+    pub fn simplify_with_params<'c>(&'c self, t1: &Ast<'c>, params: &Params<'c>) -> Ast<'c> {
+        self.try_simplify_with_params(t1, params).unwrap()
     }
+
+    /// An equivalent form of `t1`, simplified as configured by `params`.
+    /// Fails if `params` holds a parameter the simplifier does not know.
+    pub fn try_simplify_with_params<'c>(
+        &'c self,
+        t1: &Ast<'c>,
+        params: &Params<'c>,
+    ) -> Result<Ast<'c>> {
+        self.check_context(t1)?;
+        self.check_context(params)?;
+        let valid = unsafe {
+            let param_descrs = z3_sys::Z3_simplify_get_param_descrs(self.context);
+            z3_sys::Z3_param_descrs_inc_ref(self.context, param_descrs);
+            z3_sys::Z3_params_validate(self.context, params.params, param_descrs);
+            // Read the error before the next call into Z3 clears it.
+            let valid = self.check_error();
+            z3_sys::Z3_param_descrs_dec_ref(self.context, param_descrs);
+            valid
+        };
+        valid?;
+        Ast::try_new(self, unsafe {
+            z3_sys::Z3_simplify_ex(self.context, t1.ast, params.params)
+        })
+    }
+
+    builder!(
+        /// Sign extend `rhs` by `i` additional bytes. To sign-extend a 50-bit value
+        /// to a 60-bit value, `i` would be `10`.
+        sign_ext,
+        try_sign_ext,
+        Z3_mk_sign_ext,
+        (i: u32, rhs: &Ast<'c>),
+        (i, rhs.ast)
+    );
+
+    builder!(
+        /// Zero extend `rhs` by `i` additional bytes. To zero-extend a 50-bit value
+        /// to a 60-bit value, `i` would be `10`.
+        zero_ext,
+        try_zero_ext,
+        Z3_mk_zero_ext,
+        (i: u32, rhs: &Ast<'c>),
+        (i, rhs.ast)
+    );
+
+    builder!(
+        /// `array` with `value` written at `index`.
+        store,
+        try_store,
+        Z3_mk_store,
+        (array: &Ast<'c>, index: &Ast<'c>, value: &Ast<'c>),
+        (array.ast, index.ast, value.ast)
+    );
+
+    binary_builder!(xor, try_xor, Z3_mk_xor);
 }
 
 impl Drop for Context {
@@ -206,3 +778,156 @@ impl Drop for Context {
         }
     }
 }
+
+#[test]
+fn test_bv_operators() {
+    use Model;
+    use Solver;
+
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+    let model = Model::new(&context, &solver).unwrap();
+
+    let eval = |ast: Ast| -> u64 {
+        model
+            .get_const_interp(&ast)
+            .unwrap()
+            .get_numeral_decimal_string()
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    let eval_bool =
+        |ast: Ast| -> bool { model.get_const_interp(&ast).unwrap().to_string().unwrap() == "true" };
+
+    let sort8 = context.mk_bv_sort(8);
+    let a = context.mk_numeral(0xb4, &sort8).unwrap();
+    let b = context.mk_numeral(0x06, &sort8).unwrap();
+    let three = context.mk_numeral(3, &sort8).unwrap();
+
+    assert_eq!(eval(context.bvneg(&a)), 0x4c);
+    assert_eq!(
+        eval(context.bvashr(&a, &context.mk_numeral(2, &sort8).unwrap())),
+        0xed
+    );
+    assert_eq!(eval(context.bvnand(&a, &b)), 0xfb);
+    assert_eq!(eval(context.bvnor(&a, &b)), 0x49);
+    assert_eq!(eval(context.bvxnor(&a, &b)), 0x4d);
+    assert_eq!(eval(context.bvsmod(&a, &b)), 0x02);
+    assert_eq!(eval(context.bvsrem(&a, &b)), 0xfc);
+    assert_eq!(eval(context.rotate_left(3, &a)), 0xa5);
+    assert_eq!(eval(context.rotate_right(3, &a)), 0x96);
+    assert_eq!(eval(context.rotate_left_ext(&a, &three)), 0xa5);
+    assert_eq!(eval(context.rotate_right_ext(&a, &three)), 0x96);
+    assert_eq!(eval(context.repeat(2, &a)), 0xb4b4);
+    assert_eq!(eval(context.bvredand(&a)), 0);
+    assert_eq!(eval(context.bvredor(&a)), 1);
+    assert_eq!(
+        eval(context.bvredand(&context.bvor(&context.bvnot(&b), &b))),
+        1
+    );
+
+    assert!(eval_bool(context.bvuge(&a, &b)));
+    assert!(eval_bool(context.bvugt(&a, &b)));
+    assert!(!eval_bool(context.bvule(&a, &b)));
+    assert!(eval_bool(context.bvule(&a, &a)));
+    assert!(!eval_bool(context.bvult(&a, &a)));
+    assert!(!eval_bool(context.bvsge(&a, &b)));
+    assert!(!eval_bool(context.bvsgt(&a, &b)));
+    assert!(eval_bool(context.bvsle(&a, &b)));
+    assert!(eval_bool(context.bvslt(&a, &b)));
+}
+
+#[test]
+fn test_arrays() {
+    use Check;
+    use Model;
+    use Solver;
+
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+    let model = Model::new(&context, &solver).unwrap();
+
+    let eval = |ast: Ast| -> u64 {
+        model
+            .get_const_interp(&ast)
+            .unwrap()
+            .as_biguint()
+            .unwrap()
+            .to_u64_digits()
+            .first()
+            .cloned()
+            .unwrap_or(0)
+    };
+
+    let sort32 = context.mk_bv_sort(32);
+    let sort8 = context.mk_bv_sort(8);
+    let zero = context.mk_numeral(0, &sort8).unwrap();
+    let five = context.mk_numeral(5, &sort32).unwrap();
+    let six = context.mk_numeral(6, &sort32).unwrap();
+    let seven = context.mk_numeral(7, &sort8).unwrap();
+
+    let array = context.store(&context.const_array(&sort32, &zero), &five, &seven);
+    assert_eq!(eval(context.select(&array, &five)), 7);
+    assert_eq!(eval(context.select(&array, &six)), 0);
+
+    let default = context.array_default(&context.const_array(&sort32, &seven));
+    solver.assert(&context.not(&context.eq(&default, &seven)));
+    assert_eq!(solver.check(), Check::Unsat);
+}
+
+#[test]
+fn test_wide_numerals() {
+    use num_bigint::BigUint;
+
+    let context = Context::new(Config::new());
+
+    let sort80 = context.mk_bv_sort(80);
+    let value = (BigUint::from(0xabcdu64) << 64) + BigUint::from(0x1234u64);
+    let numeral = context.mk_numeral_biguint(&value, &sort80).unwrap();
+    assert_eq!(numeral.as_biguint().unwrap(), value);
+
+    let numeral = context.mk_numeral_str("1208925819614629174706175", &sort80);
+    assert_eq!(
+        numeral.unwrap().as_biguint().unwrap(),
+        (BigUint::from(1u64) << 80) - BigUint::from(1u64)
+    );
+    assert!(context.mk_numeral_str("not a number", &sort80).is_err());
+
+    let bits = [true, false, true, true, false];
+    let numeral = context.mk_bv_from_bits(&bits).unwrap();
+    assert_eq!(numeral.as_biguint().unwrap(), BigUint::from(0b01101u64));
+}
+
+#[test]
+fn test_parse_smt2() -> Result<()> {
+    let context = Context::new(Config::new());
+
+    let assertions = context.parse_smt2_string(
+        "(declare-const a (_ BitVec 8)) (assert (bvult a #x10)) (assert (= a #x07))",
+        &[],
+        &[],
+    )?;
+    assert_eq!(assertions.len(), 2);
+    assert_eq!(assertions[1].to_string().unwrap(), "(= a #x07)");
+
+    // Names the benchmark uses without declaring come from the tables.
+    let sort8 = context.mk_bv_sort(8);
+    let f = context.mk_func_decl("f", &[&sort8], &sort8)?;
+    let word = Sort::array(&sort8, &sort8);
+    let assertions = context.parse_smt2_string(
+        "(declare-const m Word) (assert (= (f (select m #x00)) #x01))",
+        &[("Word", &word)],
+        &[("f", &f)],
+    )?;
+    assert_eq!(assertions.len(), 1);
+
+    assert!(context
+        .parse_smt2_string("(assert (= b #x07))", &[], &[])
+        .is_err());
+    assert!(context
+        .parse_smt2_file("/nonexistent/benchmark.smt2", &[], &[])
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use z3_sys;
+
+/// The operation a Z3 function declaration stands for.
+///
+/// Bitvector operations are named after the `Context` builders which create
+/// them. Operations this crate does not interpret are `Other`, holding the
+/// raw `Z3_decl_kind`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DeclKind {
+    True,
+    False,
+    Eq,
+    Distinct,
+    Ite,
+    And,
+    Or,
+    Iff,
+    Xor,
+    Not,
+    Implies,
+    Store,
+    Select,
+    ConstArray,
+    ArrayDefault,
+    /// An array defined by a function interpretation in a model.
+    AsArray,
+    /// A bitvector numeral.
+    BvNumeral,
+    /// The bitvector `1` of size 1.
+    Bit1,
+    /// The bitvector `0` of size 1.
+    Bit0,
+    Bvneg,
+    Bvadd,
+    Bvsub,
+    Bvmul,
+    Bvsdiv,
+    Bvudiv,
+    Bvsrem,
+    Bvurem,
+    Bvsmod,
+    /// `bvsdiv` of a divisor of zero.
+    BvsdivZero,
+    /// `bvudiv` of a divisor of zero.
+    BvudivZero,
+    /// `bvsrem` of a divisor of zero.
+    BvsremZero,
+    /// `bvurem` of a divisor of zero.
+    BvuremZero,
+    /// `bvsmod` of a divisor of zero.
+    BvsmodZero,
+    /// `bvsdiv` of a divisor known not to be zero.
+    BvsdivUnchecked,
+    /// `bvudiv` of a divisor known not to be zero.
+    BvudivUnchecked,
+    /// `bvsrem` of a divisor known not to be zero.
+    BvsremUnchecked,
+    /// `bvurem` of a divisor known not to be zero.
+    BvuremUnchecked,
+    /// `bvsmod` of a divisor known not to be zero.
+    BvsmodUnchecked,
+    Bvule,
+    Bvsle,
+    Bvuge,
+    Bvsge,
+    Bvult,
+    Bvslt,
+    Bvugt,
+    Bvsgt,
+    Bvand,
+    Bvor,
+    Bvnot,
+    Bvxor,
+    Bvnand,
+    Bvnor,
+    Bvxnor,
+    Concat,
+    SignExt,
+    ZeroExt,
+    Extract,
+    Repeat,
+    Bvredor,
+    Bvredand,
+    /// The bitvector `1` of size 1 if the arguments are equal, and `0` if
+    /// not.
+    Bvcomp,
+    Bvshl,
+    Bvlshr,
+    Bvashr,
+    RotateLeft,
+    RotateRight,
+    RotateLeftExt,
+    RotateRightExt,
+    /// A single bit of a bitvector, as a Boolean.
+    Bit2Bool,
+    Int2Bv,
+    Bv2Int,
+    /// The carry of a full adder over three bits.
+    Carry,
+    /// The exclusive or of three bits.
+    Xor3,
+    BvsmulNoOverflow,
+    BvumulNoOverflow,
+    BvsmulNoUnderflow,
+    /// A step of a proof.
+    Proof(ProofRule),
+    /// A function or constant declared by the user.
+    Uninterpreted,
+    Other(u32),
+}
+
+impl DeclKind {
+    pub(crate) fn from_z3(kind: z3_sys::DeclKind) -> DeclKind {
+        match kind {
+            z3_sys::DeclKind::TRUE => DeclKind::True,
+            z3_sys::DeclKind::FALSE => DeclKind::False,
+            z3_sys::DeclKind::EQ => DeclKind::Eq,
+            z3_sys::DeclKind::DISTINCT => DeclKind::Distinct,
+            z3_sys::DeclKind::ITE => DeclKind::Ite,
+            z3_sys::DeclKind::AND => DeclKind::And,
+            z3_sys::DeclKind::OR => DeclKind::Or,
+            z3_sys::DeclKind::IFF => DeclKind::Iff,
+            z3_sys::DeclKind::XOR => DeclKind::Xor,
+            z3_sys::DeclKind::NOT => DeclKind::Not,
+            z3_sys::DeclKind::IMPLIES => DeclKind::Implies,
+            z3_sys::DeclKind::STORE => DeclKind::Store,
+            z3_sys::DeclKind::SELECT => DeclKind::Select,
+            z3_sys::DeclKind::CONST_ARRAY => DeclKind::ConstArray,
+            z3_sys::DeclKind::ARRAY_DEFAULT => DeclKind::ArrayDefault,
+            z3_sys::DeclKind::AS_ARRAY => DeclKind::AsArray,
+            z3_sys::DeclKind::BNUM => DeclKind::BvNumeral,
+            z3_sys::DeclKind::BIT1 => DeclKind::Bit1,
+            z3_sys::DeclKind::BIT0 => DeclKind::Bit0,
+            z3_sys::DeclKind::BNEG => DeclKind::Bvneg,
+            z3_sys::DeclKind::BADD => DeclKind::Bvadd,
+            z3_sys::DeclKind::BSUB => DeclKind::Bvsub,
+            z3_sys::DeclKind::BMUL => DeclKind::Bvmul,
+            z3_sys::DeclKind::BSDIV => DeclKind::Bvsdiv,
+            z3_sys::DeclKind::BUDIV => DeclKind::Bvudiv,
+            z3_sys::DeclKind::BSREM => DeclKind::Bvsrem,
+            z3_sys::DeclKind::BUREM => DeclKind::Bvurem,
+            z3_sys::DeclKind::BSMOD => DeclKind::Bvsmod,
+            z3_sys::DeclKind::BSDIV0 => DeclKind::BvsdivZero,
+            z3_sys::DeclKind::BUDIV0 => DeclKind::BvudivZero,
+            z3_sys::DeclKind::BSREM0 => DeclKind::BvsremZero,
+            z3_sys::DeclKind::BUREM0 => DeclKind::BvuremZero,
+            z3_sys::DeclKind::BSMOD0 => DeclKind::BvsmodZero,
+            z3_sys::DeclKind::BSDIV_I => DeclKind::BvsdivUnchecked,
+            z3_sys::DeclKind::BUDIV_I => DeclKind::BvudivUnchecked,
+            z3_sys::DeclKind::BSREM_I => DeclKind::BvsremUnchecked,
+            z3_sys::DeclKind::BUREM_I => DeclKind::BvuremUnchecked,
+            z3_sys::DeclKind::BSMOD_I => DeclKind::BvsmodUnchecked,
+            z3_sys::DeclKind::ULEQ => DeclKind::Bvule,
+            z3_sys::DeclKind::SLEQ => DeclKind::Bvsle,
+            z3_sys::DeclKind::UGEQ => DeclKind::Bvuge,
+            z3_sys::DeclKind::SGEQ => DeclKind::Bvsge,
+            z3_sys::DeclKind::ULT => DeclKind::Bvult,
+            z3_sys::DeclKind::SLT => DeclKind::Bvslt,
+            z3_sys::DeclKind::UGT => DeclKind::Bvugt,
+            z3_sys::DeclKind::SGT => DeclKind::Bvsgt,
+            z3_sys::DeclKind::BAND => DeclKind::Bvand,
+            z3_sys::DeclKind::BOR => DeclKind::Bvor,
+            z3_sys::DeclKind::BNOT => DeclKind::Bvnot,
+            z3_sys::DeclKind::BXOR => DeclKind::Bvxor,
+            z3_sys::DeclKind::BNAND => DeclKind::Bvnand,
+            z3_sys::DeclKind::BNOR => DeclKind::Bvnor,
+            z3_sys::DeclKind::BXNOR => DeclKind::Bvxnor,
+            z3_sys::DeclKind::CONCAT => DeclKind::Concat,
+            z3_sys::DeclKind::SIGN_EXT => DeclKind::SignExt,
+            z3_sys::DeclKind::ZERO_EXT => DeclKind::ZeroExt,
+            z3_sys::DeclKind::EXTRACT => DeclKind::Extract,
+            z3_sys::DeclKind::REPEAT => DeclKind::Repeat,
+            z3_sys::DeclKind::BREDOR => DeclKind::Bvredor,
+            z3_sys::DeclKind::BREDAND => DeclKind::Bvredand,
+            z3_sys::DeclKind::BCOMP => DeclKind::Bvcomp,
+            z3_sys::DeclKind::BSHL => DeclKind::Bvshl,
+            z3_sys::DeclKind::BLSHR => DeclKind::Bvlshr,
+            z3_sys::DeclKind::BASHR => DeclKind::Bvashr,
+            z3_sys::DeclKind::ROTATE_LEFT => DeclKind::RotateLeft,
+            z3_sys::DeclKind::ROTATE_RIGHT => DeclKind::RotateRight,
+            z3_sys::DeclKind::EXT_ROTATE_LEFT => DeclKind::RotateLeftExt,
+            z3_sys::DeclKind::EXT_ROTATE_RIGHT => DeclKind::RotateRightExt,
+            z3_sys::DeclKind::BIT2BOOL => DeclKind::Bit2Bool,
+            z3_sys::DeclKind::INT2BV => DeclKind::Int2Bv,
+            z3_sys::DeclKind::BV2INT => DeclKind::Bv2Int,
+            z3_sys::DeclKind::CARRY => DeclKind::Carry,
+            z3_sys::DeclKind::XOR3 => DeclKind::Xor3,
+            z3_sys::DeclKind::BSMUL_NO_OVFL => DeclKind::BvsmulNoOverflow,
+            z3_sys::DeclKind::BUMUL_NO_OVFL => DeclKind::BvumulNoOverflow,
+            z3_sys::DeclKind::BSMUL_NO_UDFL => DeclKind::BvsmulNoUnderflow,
+            z3_sys::DeclKind::UNINTERPRETED => DeclKind::Uninterpreted,
+            kind => match ProofRule::from_z3(kind) {
+                Some(rule) => DeclKind::Proof(rule),
+                None => DeclKind::Other(kind as u32),
+            },
+        }
+    }
+}
+
+/// The rule a step of a proof applies, as the declaration kind of a proof
+/// term.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProofRule {
+    /// An undefined proof, from a proof which Z3 could not complete.
+    Undef,
+    /// A proof of `true`.
+    True,
+    /// A formula asserted in the solver.
+    Asserted,
+    /// A formula from the goal a tactic was applied to.
+    Goal,
+    /// `q` from proofs of `p` and `p implies q`.
+    ModusPonens,
+    Reflexivity,
+    Symmetry,
+    Transitivity,
+    TransitivityStar,
+    Monotonicity,
+    QuantIntro,
+    Bind,
+    Distributivity,
+    AndElim,
+    NotOrElim,
+    /// An equality proved by the rewriter.
+    Rewrite,
+    RewriteStar,
+    PullQuant,
+    PushQuant,
+    ElimUnusedVars,
+    Der,
+    QuantInst,
+    /// A hypothesis, discharged by an enclosing `Lemma`.
+    Hypothesis,
+    /// The negation of the hypotheses used to prove `false`.
+    Lemma,
+    /// Resolution of a clause against unit clauses.
+    UnitResolution,
+    IffTrue,
+    IffFalse,
+    Commutativity,
+    DefAxiom,
+    DefIntro,
+    ApplyDef,
+    IffOeq,
+    NnfPos,
+    NnfNeg,
+    Skolemize,
+    ModusPonensOeq,
+    /// A lemma of a theory solver, such as bitvectors.
+    ThLemma,
+    HyperResolve,
+}
+
+impl ProofRule {
+    pub(crate) fn from_z3(kind: z3_sys::DeclKind) -> Option<ProofRule> {
+        Some(match kind {
+            z3_sys::DeclKind::PR_UNDEF => ProofRule::Undef,
+            z3_sys::DeclKind::PR_TRUE => ProofRule::True,
+            z3_sys::DeclKind::PR_ASSERTED => ProofRule::Asserted,
+            z3_sys::DeclKind::PR_GOAL => ProofRule::Goal,
+            z3_sys::DeclKind::PR_MODUS_PONENS => ProofRule::ModusPonens,
+            z3_sys::DeclKind::PR_REFLEXIVITY => ProofRule::Reflexivity,
+            z3_sys::DeclKind::PR_SYMMETRY => ProofRule::Symmetry,
+            z3_sys::DeclKind::PR_TRANSITIVITY => ProofRule::Transitivity,
+            z3_sys::DeclKind::PR_TRANSITIVITY_STAR => ProofRule::TransitivityStar,
+            z3_sys::DeclKind::PR_MONOTONICITY => ProofRule::Monotonicity,
+            z3_sys::DeclKind::PR_QUANT_INTRO => ProofRule::QuantIntro,
+            z3_sys::DeclKind::PR_BIND => ProofRule::Bind,
+            z3_sys::DeclKind::PR_DISTRIBUTIVITY => ProofRule::Distributivity,
+            z3_sys::DeclKind::PR_AND_ELIM => ProofRule::AndElim,
+            z3_sys::DeclKind::PR_NOT_OR_ELIM => ProofRule::NotOrElim,
+            z3_sys::DeclKind::PR_REWRITE => ProofRule::Rewrite,
+            z3_sys::DeclKind::PR_REWRITE_STAR => ProofRule::RewriteStar,
+            z3_sys::DeclKind::PR_PULL_QUANT => ProofRule::PullQuant,
+            z3_sys::DeclKind::PR_PUSH_QUANT => ProofRule::PushQuant,
+            z3_sys::DeclKind::PR_ELIM_UNUSED_VARS => ProofRule::ElimUnusedVars,
+            z3_sys::DeclKind::PR_DER => ProofRule::Der,
+            z3_sys::DeclKind::PR_QUANT_INST => ProofRule::QuantInst,
+            z3_sys::DeclKind::PR_HYPOTHESIS => ProofRule::Hypothesis,
+            z3_sys::DeclKind::PR_LEMMA => ProofRule::Lemma,
+            z3_sys::DeclKind::PR_UNIT_RESOLUTION => ProofRule::UnitResolution,
+            z3_sys::DeclKind::PR_IFF_TRUE => ProofRule::IffTrue,
+            z3_sys::DeclKind::PR_IFF_FALSE => ProofRule::IffFalse,
+            z3_sys::DeclKind::PR_COMMUTATIVITY => ProofRule::Commutativity,
+            z3_sys::DeclKind::PR_DEF_AXIOM => ProofRule::DefAxiom,
+            z3_sys::DeclKind::PR_DEF_INTRO => ProofRule::DefIntro,
+            z3_sys::DeclKind::PR_APPLY_DEF => ProofRule::ApplyDef,
+            z3_sys::DeclKind::PR_IFF_OEQ => ProofRule::IffOeq,
+            z3_sys::DeclKind::PR_NNF_POS => ProofRule::NnfPos,
+            z3_sys::DeclKind::PR_NNF_NEG => ProofRule::NnfNeg,
+            z3_sys::DeclKind::PR_SKOLEMIZE => ProofRule::Skolemize,
+            z3_sys::DeclKind::PR_MODUS_PONENS_OEQ => ProofRule::ModusPonensOeq,
+            z3_sys::DeclKind::PR_TH_LEMMA => ProofRule::ThLemma,
+            z3_sys::DeclKind::PR_HYPER_RESOLVE => ProofRule::HyperResolve,
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,140 @@
+use context::InContext;
+use error::*;
+use std::ffi::CStr;
+use z3_sys;
+use Ast;
+use Context;
+use DeclKind;
+use Sort;
+
+/// A reference-counted handle to a Z3 function declaration, such as an
+/// uninterpreted function, bound to the lifetime of the `Context` that
+/// created it.
+pub struct FuncDecl<'c> {
+    pub(crate) func_decl: z3_sys::Z3_func_decl,
+    pub(crate) context: &'c Context,
+}
+
+impl<'c> InContext for FuncDecl<'c> {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        Some(self.context.context)
+    }
+}
+
+impl<'c> FuncDecl<'c> {
+    /// Wrap a raw `Z3_func_decl`, taking a reference to it.
+    pub(crate) fn new(context: &'c Context, func_decl: z3_sys::Z3_func_decl) -> FuncDecl<'c> {
+        unsafe {
+            z3_sys::Z3_inc_ref(
+                context.context,
+                z3_sys::Z3_func_decl_to_ast(context.context, func_decl),
+            );
+        }
+        FuncDecl { func_decl, context }
+    }
+
+    /// Wrap a raw `Z3_func_decl` returned by a call which may have failed.
+    pub(crate) fn try_new(
+        context: &'c Context,
+        func_decl: z3_sys::Z3_func_decl,
+    ) -> Result<FuncDecl<'c>> {
+        context.check_error()?;
+        Ok(FuncDecl::new(context, func_decl))
+    }
+
+    /// The name of this function.
+    pub fn name(&self) -> String {
+        unsafe {
+            let symbol = z3_sys::Z3_get_decl_name(self.context.context, self.func_decl);
+            match z3_sys::Z3_get_symbol_kind(self.context.context, symbol) {
+                z3_sys::SymbolKind::String => {
+                    let s = z3_sys::Z3_get_symbol_string(self.context.context, symbol);
+                    CStr::from_ptr(s).to_string_lossy().into_owned()
+                }
+                z3_sys::SymbolKind::Int => {
+                    z3_sys::Z3_get_symbol_int(self.context.context, symbol).to_string()
+                }
+            }
+        }
+    }
+
+    /// The operation this function stands for.
+    pub fn kind(&self) -> DeclKind {
+        DeclKind::from_z3(unsafe { z3_sys::Z3_get_decl_kind(self.context.context, self.func_decl) })
+    }
+
+    /// The integer parameters of this function, such as the high and low
+    /// bits of an `Extract`, or the number of bits added by a `ZeroExt`.
+    pub fn int_parameters(&self) -> Vec<i32> {
+        let num_parameters =
+            unsafe { z3_sys::Z3_get_decl_num_parameters(self.context.context, self.func_decl) };
+        (0..num_parameters)
+            .filter(|&i| unsafe {
+                z3_sys::Z3_get_decl_parameter_kind(self.context.context, self.func_decl, i)
+                    == z3_sys::ParameterKind::Int
+            })
+            .map(|i| unsafe {
+                z3_sys::Z3_get_decl_int_parameter(self.context.context, self.func_decl, i)
+            })
+            .collect()
+    }
+
+    /// The number of arguments this function takes.
+    pub fn arity(&self) -> usize {
+        unsafe { z3_sys::Z3_get_arity(self.context.context, self.func_decl) as usize }
+    }
+
+    /// The sort of the `i`th argument of this function.
+    pub fn domain(&self, i: usize) -> Option<Sort<'c>> {
+        if i >= self.arity() {
+            None
+        } else {
+            Some(Sort::new(self.context, unsafe {
+                z3_sys::Z3_get_domain(self.context.context, self.func_decl, i as u32)
+            }))
+        }
+    }
+
+    /// The sort of the result of this function.
+    pub fn range(&self) -> Sort<'c> {
+        Sort::new(self.context, unsafe {
+            z3_sys::Z3_get_range(self.context.context, self.func_decl)
+        })
+    }
+
+    /// Apply this function to `args`.
+    pub fn apply(&self, args: &[&Ast<'c>]) -> Ast<'c> {
+        self.try_apply(args).unwrap()
+    }
+
+    /// Apply this function to `args`.
+    pub fn try_apply(&self, args: &[&Ast<'c>]) -> Result<Ast<'c>> {
+        self.context.check_contexts(args)?;
+        let args: Vec<z3_sys::Z3_ast> = args.iter().map(|arg| arg.ast).collect();
+        Ast::try_new(self.context, unsafe {
+            z3_sys::Z3_mk_app(
+                self.context.context,
+                self.func_decl,
+                args.len() as u32,
+                args.as_ptr(),
+            )
+        })
+    }
+}
+
+impl<'c> Clone for FuncDecl<'c> {
+    fn clone(&self) -> FuncDecl<'c> {
+        FuncDecl::new(self.context, self.func_decl)
+    }
+}
+
+impl<'c> Drop for FuncDecl<'c> {
+    fn drop(&mut self) {
+        unsafe {
+            z3_sys::Z3_dec_ref(
+                self.context.context,
+                z3_sys::Z3_func_decl_to_ast(self.context.context, self.func_decl),
+            )
+        }
+    }
+}
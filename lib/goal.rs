@@ -0,0 +1,82 @@
+use error::*;
+use std::ffi::CStr;
+use z3_sys;
+use Ast;
+use Context;
+
+/// A set of formulas for a `Tactic` to transform.
+pub struct Goal<'c> {
+    pub(crate) goal: z3_sys::Z3_goal,
+    context: &'c Context,
+}
+
+impl<'c> Goal<'c> {
+    /// Create an empty goal. Tactics applied to it keep enough information
+    /// to build models.
+    pub fn new(context: &'c Context) -> Goal<'c> {
+        Goal::from_raw(context, unsafe {
+            z3_sys::Z3_mk_goal(context.context, true, false, false)
+        })
+    }
+
+    /// Wrap a raw `Z3_goal`, taking a reference to it.
+    pub(crate) fn from_raw(context: &'c Context, goal: z3_sys::Z3_goal) -> Goal<'c> {
+        unsafe {
+            z3_sys::Z3_goal_inc_ref(context.context, goal);
+        }
+        Goal { goal, context }
+    }
+
+    pub fn assert(&self, formula: &Ast<'c>) {
+        self.try_assert(formula).unwrap()
+    }
+
+    /// Assert `formula`, failing if it is not a Boolean term.
+    pub fn try_assert(&self, formula: &Ast<'c>) -> Result<()> {
+        self.context.check_context(formula)?;
+        unsafe { z3_sys::Z3_goal_assert(self.context.context, self.goal, formula.ast) }
+        self.context.check_error()
+    }
+
+    /// The formulas in this goal.
+    pub fn formulas(&self) -> Vec<Ast<'c>> {
+        (0..self.size())
+            .map(|i| {
+                Ast::new(self.context, unsafe {
+                    z3_sys::Z3_goal_formula(self.context.context, self.goal, i as u32)
+                })
+            })
+            .collect()
+    }
+
+    /// The number of formulas in this goal.
+    pub fn size(&self) -> usize {
+        unsafe { z3_sys::Z3_goal_size(self.context.context, self.goal) as usize }
+    }
+
+    /// True if this goal is empty, and therefore satisfiable.
+    pub fn is_decided_sat(&self) -> bool {
+        unsafe { z3_sys::Z3_goal_is_decided_sat(self.context.context, self.goal) }
+    }
+
+    /// True if this goal contains `false`, and therefore is unsatisfiable.
+    pub fn is_decided_unsat(&self) -> bool {
+        unsafe { z3_sys::Z3_goal_is_decided_unsat(self.context.context, self.goal) }
+    }
+
+    pub fn to_string(&self) -> Option<String> {
+        let s = unsafe { z3_sys::Z3_goal_to_string(self.context.context, self.goal) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            cs.to_str().ok().map(|s| s.to_string())
+        }
+    }
+}
+
+impl<'c> Drop for Goal<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_goal_dec_ref(self.context.context, self.goal) }
+    }
+}
@@ -1,12 +1,16 @@
 use error::*;
 use falcon::il;
-use std::collections::HashMap;
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use Ast;
 use Check;
 use Config;
 use Context;
+use DeclKind;
 use Model;
 use Optimize;
+use Params;
 use Solver;
 
 pub enum SolverResult {
@@ -15,61 +19,61 @@ pub enum SolverResult {
     Sat(il::Constant),
 }
 
-fn return_solver_result(
-    solver: &Solver,
-    context: &Context,
-    ast: &Ast,
+fn return_solver_result<'c>(
+    solver: &Solver<'c>,
+    context: &'c Context,
+    ast: &Ast<'c>,
     bits: usize,
 ) -> Option<il::Constant> {
     match solver.check() {
         Check::Unsat | Check::Unknown => None,
         Check::Sat => Model::new(context, solver)
             .and_then(|model| model.get_const_interp(ast))
-            .and_then(|constant_ast| constant_ast.get_numeral_decimal_string(context))
-            .and_then(|numeral_dec_str| {
-                il::Constant::from_decimal_string(&numeral_dec_str, bits).ok()
-            }),
+            .and_then(|constant_ast| constant_ast.as_biguint())
+            .map(|value| il::Constant::new_big(value, bits)),
     }
 }
 
-fn return_optimize_result(
-    optimize: &Optimize,
-    context: &Context,
-    ast: &Ast,
+fn return_optimize_result<'c>(
+    optimize: &Optimize<'c>,
+    context: &'c Context,
+    ast: &Ast<'c>,
     bits: usize,
 ) -> Option<il::Constant> {
     match optimize.check() {
         Check::Unsat | Check::Unknown => None,
         Check::Sat => Model::new_optimize(context, optimize)
             .and_then(|model| model.get_const_interp(ast))
-            .and_then(|constant_ast| constant_ast.get_numeral_decimal_string(context))
-            .and_then(|numeral_dec_str| {
-                il::Constant::from_decimal_string(&numeral_dec_str, bits).ok()
-            }),
+            .and_then(|constant_ast| constant_ast.as_biguint())
+            .map(|value| il::Constant::new_big(value, bits)),
     }
 }
 
-fn solver_init(solver: &Solver, context: &Context, constraints: &[il::Expression]) -> Result<()> {
+fn solver_init<'c>(
+    solver: &Solver<'c>,
+    context: &'c Context,
+    constraints: &[il::Expression],
+) -> Result<()> {
     let sort = context.mk_bv_sort(1);
     let one = context.mk_numeral(1, &sort)?;
 
     for constraint in constraints {
-        solver.assert(&context.eq(&one, &expression_to_ast(context, constraint)?));
+        solver.try_assert(&context.try_eq(&one, &expression_to_ast(context, constraint)?)?)?;
     }
 
     Ok(())
 }
 
-fn optimize_init(
-    optimize: &Optimize,
-    context: &Context,
+fn optimize_init<'c>(
+    optimize: &Optimize<'c>,
+    context: &'c Context,
     constraints: &[il::Expression],
 ) -> Result<()> {
     let sort = context.mk_bv_sort(1);
     let one = context.mk_numeral(1, &sort)?;
 
     for constraint in constraints {
-        optimize.assert(&context.eq(&one, &expression_to_ast(context, constraint)?));
+        optimize.try_assert(&context.try_eq(&one, &expression_to_ast(context, constraint)?)?)?;
     }
 
     Ok(())
@@ -85,11 +89,13 @@ pub fn maximize(
 
     optimize_init(&optimize, &context, constraints)?;
 
-    let optimize_result = context.mk_var("OPTIMIZE_RESULT", &context.mk_bv_sort(value.bits()))?;
+    let optimize_result =
+        context.mk_var("OPTIMIZE_RESULT", &context.try_mk_bv_sort(value.bits())?)?;
 
-    optimize.assert(&context.eq(&optimize_result, &expression_to_ast(&context, value)?));
+    optimize
+        .try_assert(&context.try_eq(&optimize_result, &expression_to_ast(&context, value)?)?)?;
 
-    optimize.maximize(&optimize_result);
+    optimize.try_maximize(&optimize_result)?;
 
     Ok(return_optimize_result(
         &optimize,
@@ -109,11 +115,13 @@ pub fn minimize(
 
     optimize_init(&optimize, &context, constraints)?;
 
-    let optimize_result = context.mk_var("OPTIMIZE_RESULT", &context.mk_bv_sort(value.bits()))?;
+    let optimize_result =
+        context.mk_var("OPTIMIZE_RESULT", &context.try_mk_bv_sort(value.bits())?)?;
 
-    optimize.assert(&context.eq(&optimize_result, &expression_to_ast(&context, value)?));
+    optimize
+        .try_assert(&context.try_eq(&optimize_result, &expression_to_ast(&context, value)?)?)?;
 
-    optimize.minimize(&optimize_result);
+    optimize.try_minimize(&optimize_result)?;
 
     Ok(return_optimize_result(
         &optimize,
@@ -123,6 +131,264 @@ pub fn minimize(
     ))
 }
 
+/// The result of `maximize_anytime` or `minimize_anytime`. The optimum lies
+/// between `value` and `bound`, inclusive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnytimeResult {
+    value: il::Constant,
+    bound: il::Constant,
+}
+
+impl AnytimeResult {
+    /// The best value found which satisfies the constraints.
+    pub fn value(&self) -> &il::Constant {
+        &self.value
+    }
+
+    /// No value better than this satisfies the constraints.
+    pub fn bound(&self) -> &il::Constant {
+        &self.bound
+    }
+
+    /// True if `value` is the optimum.
+    pub fn is_optimal(&self) -> bool {
+        self.value == self.bound
+    }
+}
+
+/// Maximize `value` under `constraints`, giving up after `milliseconds`
+/// with the best value found so far.
+///
+/// Returns `None` if the constraints are unsatisfiable, or if no value was
+/// found before the deadline.
+pub fn maximize_anytime(
+    constraints: &[il::Expression],
+    value: &il::Expression,
+    milliseconds: u32,
+) -> Result<Option<AnytimeResult>> {
+    optimize_anytime(constraints, value, milliseconds, true)
+}
+
+/// Minimize `value` under `constraints`, giving up after `milliseconds`
+/// with the best value found so far.
+///
+/// Returns `None` if the constraints are unsatisfiable, or if no value was
+/// found before the deadline.
+pub fn minimize_anytime(
+    constraints: &[il::Expression],
+    value: &il::Expression,
+    milliseconds: u32,
+) -> Result<Option<AnytimeResult>> {
+    optimize_anytime(constraints, value, milliseconds, false)
+}
+
+fn optimize_anytime(
+    constraints: &[il::Expression],
+    value: &il::Expression,
+    milliseconds: u32,
+    maximize: bool,
+) -> Result<Option<AnytimeResult>> {
+    let deadline = Instant::now() + Duration::from_millis(u64::from(milliseconds));
+    let config = Config::new().enable_model();
+    let context = Context::new(config);
+    let bits = value.bits();
+    let sort = context.try_mk_bv_sort(bits)?;
+    let result = context.mk_var("OPTIMIZE_RESULT", &sort)?;
+    let result_eq_value = context.try_eq(&result, &expression_to_ast(&context, value)?)?;
+
+    // Give the Optimize engine the first half of the time. A timeout of 0
+    // means no timeout, so give it at least a millisecond.
+    let optimize = Optimize::new(&context);
+    let params = Params::new(&context);
+    params.set_timeout((milliseconds / 2).max(1));
+    optimize.set_params(&params)?;
+    optimize_init(&optimize, &context, constraints)?;
+    optimize.try_assert(&result_eq_value)?;
+    let objective = if maximize {
+        optimize.try_maximize(&result)?
+    } else {
+        optimize.try_minimize(&result)?
+    };
+
+    let seed = match optimize.check() {
+        Check::Unsat => return Ok(None),
+        Check::Sat => {
+            let optimum = if maximize {
+                optimize.get_upper(objective)
+            } else {
+                optimize.get_lower(objective)
+            };
+            if let Some(optimum) = optimum.and_then(|optimum| optimum.as_biguint()) {
+                let optimum = il::Constant::new_big(optimum, bits);
+                return Ok(Some(AnytimeResult {
+                    value: optimum.clone(),
+                    bound: optimum,
+                }));
+            }
+            None
+        }
+        Check::Unknown => {
+            optimize_progress(&context, &optimize, constraints, &result_eq_value, &result)?
+        }
+    };
+
+    // Otherwise fall back to a search with the rest of the time, starting
+    // from whatever the Optimize engine found.
+    bitwise_search(&context, constraints, value, maximize, deadline, seed)
+}
+
+/// The value of `result` in the best model the Optimize engine found before
+/// it gave up, if that model satisfies every constraint.
+fn optimize_progress<'c>(
+    context: &'c Context,
+    optimize: &Optimize<'c>,
+    constraints: &[il::Expression],
+    result_eq_value: &Ast<'c>,
+    result: &Ast<'c>,
+) -> Result<Option<BigUint>> {
+    let model = match Model::from_last_optimize(context, optimize) {
+        Some(model) => model,
+        None => return Ok(None),
+    };
+
+    let one = context.mk_numeral(1, &context.mk_bv_sort(1))?;
+    let mut assertions = vec![result_eq_value.clone()];
+    for constraint in constraints {
+        assertions.push(context.try_eq(&one, &expression_to_ast(context, constraint)?)?);
+    }
+    let satisfied = assertions.iter().all(|assertion| {
+        model
+            .get_const_interp(assertion)
+            .and_then(|value| value.decl_kind())
+            == Some(DeclKind::True)
+    });
+
+    Ok(if satisfied {
+        model
+            .get_const_interp(result)
+            .and_then(|value| value.as_biguint())
+    } else {
+        None
+    })
+}
+
+/// Search for the optimum of `value` under `constraints` one bit at a time,
+/// from the most significant bit down, until `deadline`. `seed` is a value
+/// already known to satisfy the constraints.
+fn bitwise_search(
+    context: &Context,
+    constraints: &[il::Expression],
+    value: &il::Expression,
+    maximize: bool,
+    deadline: Instant,
+    seed: Option<BigUint>,
+) -> Result<Option<AnytimeResult>> {
+    let bits = value.bits();
+    let sort = context.try_mk_bv_sort(bits)?;
+    let result = context.mk_var("OPTIMIZE_RESULT", &sort)?;
+
+    let solver = Solver::new(context);
+    solver_init(&solver, context, constraints)?;
+    solver.try_assert(&context.try_eq(&result, &expression_to_ast(context, value)?)?)?;
+
+    // Check within the time left, returning the value of the result if sat.
+    let check = |solver: &Solver| -> Result<(Check, Option<BigUint>)> {
+        let remaining = deadline
+            .saturating_duration_since(Instant::now())
+            .as_millis();
+        if remaining == 0 {
+            return Ok((Check::Unknown, None));
+        }
+        let params = Params::new(context);
+        params.set_timeout(remaining.min(u128::from(u32::MAX)) as u32);
+        solver.set_params(&params)?;
+        Ok(match solver.check() {
+            Check::Sat => {
+                let value = Model::from_last_check(context, solver)
+                    .and_then(|model| model.get_const_interp(&result))
+                    .and_then(|value| value.as_biguint());
+                match value {
+                    Some(value) => (Check::Sat, Some(value)),
+                    None => (Check::Unknown, None),
+                }
+            }
+            check => (check, None),
+        })
+    };
+
+    let mut best = match seed {
+        Some(seed) => seed,
+        None => match check(&solver)? {
+            (Check::Sat, Some(best)) => best,
+            _ => return Ok(None),
+        },
+    };
+
+    // The bits of the optimum found so far.
+    let mut prefix = BigUint::from(0u32);
+    for bit in (0..bits).rev() {
+        let bit_value = BigUint::from(1u32) << bit;
+        let low_bits = &bit_value - BigUint::from(1u32);
+
+        // Maximizing, ask whether this bit can be set. Minimizing, ask
+        // whether it can be clear. The best value so far may answer it.
+        let candidate = if maximize {
+            &prefix | &bit_value
+        } else {
+            &prefix | &low_bits
+        };
+        let answered = if maximize {
+            best >= candidate
+        } else {
+            best <= candidate
+        };
+
+        let possible = if answered {
+            true
+        } else {
+            let candidate_ast = context.mk_numeral_biguint(&candidate, &sort)?;
+            solver.push();
+            solver.try_assert(&if maximize {
+                context.try_bvuge(&result, &candidate_ast)?
+            } else {
+                context.try_bvule(&result, &candidate_ast)?
+            })?;
+            let check_result = check(&solver)?;
+            solver.pop(1)?;
+            match check_result {
+                (Check::Sat, Some(value)) => {
+                    best = value;
+                    true
+                }
+                (Check::Unsat, _) => false,
+                // Out of time, so the remaining bits could be anything.
+                _ => {
+                    let bound = if maximize {
+                        &prefix | &bit_value | &low_bits
+                    } else {
+                        prefix
+                    };
+                    return Ok(Some(AnytimeResult {
+                        value: il::Constant::new_big(best, bits),
+                        bound: il::Constant::new_big(bound, bits),
+                    }));
+                }
+            }
+        };
+
+        // The bit is set if maximizing and it can be set, or minimizing and
+        // it cannot be clear.
+        if possible == maximize {
+            prefix |= bit_value;
+        }
+    }
+
+    Ok(Some(AnytimeResult {
+        value: il::Constant::new_big(best, bits),
+        bound: il::Constant::new_big(prefix, bits),
+    }))
+}
+
 pub fn solve(
     constraints: &[il::Expression],
     value: &il::Expression,
@@ -133,9 +399,9 @@ pub fn solve(
 
     solver_init(&solver, &context, constraints)?;
 
-    let solver_result = context.mk_var("SOLVER_RESULT", &context.mk_bv_sort(value.bits()))?;
+    let solver_result = context.mk_var("SOLVER_RESULT", &context.try_mk_bv_sort(value.bits())?)?;
 
-    solver.assert(&context.eq(&solver_result, &expression_to_ast(&context, value)?));
+    solver.try_assert(&context.try_eq(&solver_result, &expression_to_ast(&context, value)?)?)?;
 
     Ok(return_solver_result(
         &solver,
@@ -157,14 +423,17 @@ pub fn solve_multi(
     let one = context.mk_numeral(1, &sort)?;
 
     for constraint in constraints {
-        solver.assert(&context.eq(&one, &expression_to_ast(&context, constraint)?));
+        solver.try_assert(&context.try_eq(&one, &expression_to_ast(&context, constraint)?)?)?;
     }
 
     let mut solver_variables = HashMap::new();
 
     for (name, expression) in values {
-        let var = context.mk_var(name.to_string(), &context.mk_bv_sort(expression.bits()))?;
-        solver.assert(&context.eq(&var, &expression_to_ast(&context, expression)?));
+        let var = context.mk_var(
+            name.to_string(),
+            &context.try_mk_bv_sort(expression.bits())?,
+        )?;
+        solver.try_assert(&context.try_eq(&var, &expression_to_ast(&context, expression)?)?)?;
         solver_variables.insert(name.to_string(), var);
     }
 
@@ -178,9 +447,8 @@ pub fn solve_multi(
                     .map(|(name, expr)| {
                         let var = &solver_variables[name];
                         let constant_ast = model.get_const_interp(var).unwrap();
-                        let dec_str = constant_ast.get_numeral_decimal_string(&context).unwrap();
-                        let constant =
-                            il::Constant::from_decimal_string(&dec_str, expr.bits()).unwrap();
+                        let value = constant_ast.as_biguint().unwrap();
+                        let constant = il::Constant::new_big(value, expr.bits());
                         (name.to_string(), constant)
                     })
                     .collect::<HashMap<String, il::Constant>>()
@@ -189,150 +457,746 @@ pub fn solve_multi(
     })
 }
 
-pub fn expression_to_ast(context: &Context, expression: &il::Expression) -> Result<Ast> {
+/// Find values for every scalar in `constraints` which satisfy them,
+/// including scalars the constraints leave unconstrained.
+pub fn solve_scalars(
+    constraints: &[il::Expression],
+) -> Result<Option<HashMap<il::Scalar, il::Constant>>> {
+    let config = Config::new().enable_model();
+    let context = Context::new(config);
+    let solver = Solver::new(&context);
+
+    solver_init(&solver, &context, constraints)?;
+
+    let mut scalars = Vec::new();
+    for constraint in constraints {
+        scalars.append(&mut constraint.scalars());
+    }
+    scalars.sort();
+    scalars.dedup();
+
+    let model = match Model::new(&context, &solver) {
+        Some(model) => model,
+        None => return Ok(None),
+    };
+    Ok(Some(scalar_values(&context, &model, &scalars)?))
+}
+
+/// The query `solve` would make for `constraints` and `value`, as a
+/// standalone SMT-LIB2 benchmark which asks for the value of `SOLVER_RESULT`.
+pub fn query_to_smt2(constraints: &[il::Expression], value: &il::Expression) -> Result<String> {
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    solver_init(&solver, &context, constraints)?;
+
+    let solver_result = context.mk_var("SOLVER_RESULT", &context.try_mk_bv_sort(value.bits())?)?;
+
+    solver.try_assert(&context.try_eq(&solver_result, &expression_to_ast(&context, value)?)?)?;
+
+    Ok(format!(
+        "{}(get-value (SOLVER_RESULT))\n",
+        solver.to_smt2()?
+    ))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OverflowKind {
+    Signed,
+    Unsigned,
+}
+
+/// An arithmetic subterm which can overflow, found by `find_overflows`.
+#[derive(Clone, Debug)]
+pub struct Overflow {
+    expression: il::Expression,
+    kind: OverflowKind,
+    witness: HashMap<il::Scalar, il::Constant>,
+}
+
+impl Overflow {
+    /// The `Add`, `Sub` or `Mul` expression which overflows.
+    pub fn expression(&self) -> &il::Expression {
+        &self.expression
+    }
+
+    pub fn kind(&self) -> OverflowKind {
+        self.kind
+    }
+
+    /// Values for the scalars in the constraints and expression which cause
+    /// this overflow.
+    pub fn witness(&self) -> &HashMap<il::Scalar, il::Constant> {
+        &self.witness
+    }
+}
+
+fn arithmetic_subterms<'e>(expression: &'e il::Expression, subterms: &mut Vec<&'e il::Expression>) {
+    match *expression {
+        il::Expression::Scalar(_) | il::Expression::Constant(_) => {}
+        il::Expression::Add(ref lhs, ref rhs)
+        | il::Expression::Sub(ref lhs, ref rhs)
+        | il::Expression::Mul(ref lhs, ref rhs) => {
+            subterms.push(expression);
+            arithmetic_subterms(lhs, subterms);
+            arithmetic_subterms(rhs, subterms);
+        }
+        il::Expression::Divu(ref lhs, ref rhs)
+        | il::Expression::Modu(ref lhs, ref rhs)
+        | il::Expression::Divs(ref lhs, ref rhs)
+        | il::Expression::Mods(ref lhs, ref rhs)
+        | il::Expression::And(ref lhs, ref rhs)
+        | il::Expression::Or(ref lhs, ref rhs)
+        | il::Expression::Xor(ref lhs, ref rhs)
+        | il::Expression::Shl(ref lhs, ref rhs)
+        | il::Expression::Shr(ref lhs, ref rhs)
+        | il::Expression::Cmpeq(ref lhs, ref rhs)
+        | il::Expression::Cmpneq(ref lhs, ref rhs)
+        | il::Expression::Cmplts(ref lhs, ref rhs)
+        | il::Expression::Cmpltu(ref lhs, ref rhs) => {
+            arithmetic_subterms(lhs, subterms);
+            arithmetic_subterms(rhs, subterms);
+        }
+        il::Expression::Zext(_, ref rhs)
+        | il::Expression::Sext(_, ref rhs)
+        | il::Expression::Trun(_, ref rhs) => arithmetic_subterms(rhs, subterms),
+        il::Expression::Ite(ref cond, ref then, ref else_) => {
+            arithmetic_subterms(cond, subterms);
+            arithmetic_subterms(then, subterms);
+            arithmetic_subterms(else_, subterms);
+        }
+    }
+}
+
+fn scalar_values<'c>(
+    context: &'c Context,
+    model: &Model<'c>,
+    scalars: &[&il::Scalar],
+) -> Result<HashMap<il::Scalar, il::Constant>> {
+    let mut values = HashMap::new();
+    for scalar in scalars {
+        let var = context.mk_var(scalar.name(), &context.try_mk_bv_sort(scalar.bits())?)?;
+        if let Some(value) = model
+            .get_const_interp(&var)
+            .and_then(|constant_ast| constant_ast.as_biguint())
+        {
+            values.insert(
+                (*scalar).clone(),
+                il::Constant::new_big(value, scalar.bits()),
+            );
+        }
+    }
+    Ok(values)
+}
+
+/// Find every `Add`, `Sub` and `Mul` in `expression` which can overflow under
+/// `constraints`, as either a signed or an unsigned operation. Each distinct
+/// subterm is reported at most once per `OverflowKind`.
+///
+/// This over-approximates: a subterm is checked on its own, so one inside
+/// an `Ite` branch is reported even where the condition never selects it.
+pub fn find_overflows(
+    constraints: &[il::Expression],
+    expression: &il::Expression,
+) -> Result<Vec<Overflow>> {
+    let config = Config::new().enable_model();
+    let context = Context::new(config);
+
+    let mut scalars = expression.scalars();
+    for constraint in constraints {
+        scalars.append(&mut constraint.scalars());
+    }
+    scalars.sort();
+    scalars.dedup();
+
+    let mut subterms = Vec::new();
+    arithmetic_subterms(expression, &mut subterms);
+    let mut seen = HashSet::new();
+    subterms.retain(|subterm| seen.insert(*subterm));
+
+    let solver = Solver::new(&context);
+    solver_init(&solver, &context, constraints)?;
+
+    let mut overflows = Vec::new();
+
+    for subterm in subterms {
+        let (unsigned, signed) = match *subterm {
+            il::Expression::Add(ref lhs, ref rhs) => {
+                let lhs = expression_to_ast(&context, lhs)?;
+                let rhs = expression_to_ast(&context, rhs)?;
+                (
+                    context.try_bvadd_no_overflow(&lhs, &rhs, false)?,
+                    context.try_and(&[
+                        &context.try_bvadd_no_overflow(&lhs, &rhs, true)?,
+                        &context.try_bvadd_no_underflow(&lhs, &rhs)?,
+                    ])?,
+                )
+            }
+            il::Expression::Sub(ref lhs, ref rhs) => {
+                let lhs = expression_to_ast(&context, lhs)?;
+                let rhs = expression_to_ast(&context, rhs)?;
+                (
+                    context.try_bvsub_no_underflow(&lhs, &rhs, false)?,
+                    context.try_and(&[
+                        &context.try_bvsub_no_overflow(&lhs, &rhs)?,
+                        &context.try_bvsub_no_underflow(&lhs, &rhs, true)?,
+                    ])?,
+                )
+            }
+            il::Expression::Mul(ref lhs, ref rhs) => {
+                let lhs = expression_to_ast(&context, lhs)?;
+                let rhs = expression_to_ast(&context, rhs)?;
+                (
+                    context.try_bvmul_no_overflow(&lhs, &rhs, false)?,
+                    context.try_and(&[
+                        &context.try_bvmul_no_overflow(&lhs, &rhs, true)?,
+                        &context.try_bvmul_no_underflow(&lhs, &rhs)?,
+                    ])?,
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        for (kind, no_overflow) in [
+            (OverflowKind::Unsigned, unsigned),
+            (OverflowKind::Signed, signed),
+        ] {
+            solver.push();
+            solver.try_assert(&context.try_not(&no_overflow)?)?;
+
+            if let Some(model) = Model::new(&context, &solver) {
+                overflows.push(Overflow {
+                    expression: subterm.clone(),
+                    kind,
+                    witness: scalar_values(&context, &model, &scalars)?,
+                });
+            }
+
+            solver.pop(1)?;
+        }
+    }
+
+    Ok(overflows)
+}
+
+pub fn expression_to_ast<'c>(context: &'c Context, expression: &il::Expression) -> Result<Ast<'c>> {
     Ok(match *expression {
         il::Expression::Scalar(ref scalar) => {
-            let sort = context.mk_bv_sort(scalar.bits());
+            let sort = context.try_mk_bv_sort(scalar.bits())?;
             context.mk_var(scalar.name(), &sort)?
         }
         il::Expression::Constant(ref constant) => {
-            if let Some(value) = constant.value_u64() {
-                let sort = context.mk_bv_sort(constant.bits());
-                context.mk_numeral(value, &sort)?
-            } else {
-                let big_uint = constant.value();
-                let sort = context.mk_bv_sort(8);
-                let bytes = big_uint.to_bytes_le();
-                let mut v = if bytes.is_empty() {
-                    context.mk_numeral(0, &sort)?
-                } else {
-                    context.mk_numeral(bytes[0] as u64, &sort)?
-                };
-                for i in 1..(constant.bits() / 8) {
-                    let numeral = if bytes.len() <= i {
-                        context.mk_numeral(0, &sort)?
-                    } else {
-                        context.mk_numeral(bytes[i] as u64, &sort)?
-                    };
-                    v = context.concat(&numeral, &v);
-                }
-                v
-            }
+            let sort = context.try_mk_bv_sort(constant.bits())?;
+            context.mk_numeral_biguint(constant.value(), &sort)?
         }
-        il::Expression::Add(ref lhs, ref rhs) => context.bvadd(
+        il::Expression::Add(ref lhs, ref rhs) => context.try_bvadd(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Sub(ref lhs, ref rhs) => context.bvsub(
+        )?,
+        il::Expression::Sub(ref lhs, ref rhs) => context.try_bvsub(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Mul(ref lhs, ref rhs) => context.bvmul(
+        )?,
+        il::Expression::Mul(ref lhs, ref rhs) => context.try_bvmul(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Divu(ref lhs, ref rhs) => context.bvudiv(
+        )?,
+        il::Expression::Divu(ref lhs, ref rhs) => context.try_bvudiv(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Modu(ref lhs, ref rhs) => context.bvurem(
+        )?,
+        il::Expression::Modu(ref lhs, ref rhs) => context.try_bvurem(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Divs(ref lhs, ref rhs) => context.bvsdiv(
+        )?,
+        il::Expression::Divs(ref lhs, ref rhs) => context.try_bvsdiv(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Mods(ref lhs, ref rhs) => context.bvsrem(
+        )?,
+        il::Expression::Mods(ref lhs, ref rhs) => context.try_bvsrem(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::And(ref lhs, ref rhs) => context.bvand(
+        )?,
+        il::Expression::And(ref lhs, ref rhs) => context.try_bvand(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Or(ref lhs, ref rhs) => context.bvor(
+        )?,
+        il::Expression::Or(ref lhs, ref rhs) => context.try_bvor(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Xor(ref lhs, ref rhs) => context.bvxor(
+        )?,
+        il::Expression::Xor(ref lhs, ref rhs) => context.try_bvxor(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Shl(ref lhs, ref rhs) => context.bvshl(
+        )?,
+        il::Expression::Shl(ref lhs, ref rhs) => context.try_bvshl(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Shr(ref lhs, ref rhs) => context.bvlshr(
+        )?,
+        il::Expression::Shr(ref lhs, ref rhs) => context.try_bvlshr(
             &expression_to_ast(context, lhs)?,
             &expression_to_ast(context, rhs)?,
-        ),
+        )?,
         il::Expression::Cmpeq(ref lhs, ref rhs) => {
             let sort = context.mk_bv_sort(1);
-            context.ite(
-                &context.eq(
+            context.try_ite(
+                &context.try_eq(
                     &expression_to_ast(context, lhs)?,
                     &expression_to_ast(context, rhs)?,
-                ),
+                )?,
                 &context.mk_numeral(1, &sort)?,
                 &context.mk_numeral(0, &sort)?,
-            )
+            )?
         }
         il::Expression::Cmpneq(ref lhs, ref rhs) => {
             let sort = context.mk_bv_sort(1);
-            context.ite(
-                &context.eq(
+            context.try_ite(
+                &context.try_eq(
                     &expression_to_ast(context, lhs)?,
                     &expression_to_ast(context, rhs)?,
-                ),
+                )?,
                 &context.mk_numeral(0, &sort)?,
                 &context.mk_numeral(1, &sort)?,
-            )
+            )?
         }
         il::Expression::Cmplts(ref lhs, ref rhs) => {
             let sort = context.mk_bv_sort(1);
-            context.ite(
-                &context.bvslt(
+            context.try_ite(
+                &context.try_bvslt(
                     &expression_to_ast(context, lhs)?,
                     &expression_to_ast(context, rhs)?,
-                ),
+                )?,
                 &context.mk_numeral(1, &sort)?,
                 &context.mk_numeral(0, &sort)?,
-            )
+            )?
         }
         il::Expression::Cmpltu(ref lhs, ref rhs) => {
             let sort = context.mk_bv_sort(1);
-            context.ite(
-                &context.bvult(
+            context.try_ite(
+                &context.try_bvult(
                     &expression_to_ast(context, lhs)?,
                     &expression_to_ast(context, rhs)?,
-                ),
+                )?,
                 &context.mk_numeral(1, &sort)?,
                 &context.mk_numeral(0, &sort)?,
-            )
+            )?
         }
-        il::Expression::Zext(bits, ref rhs) => context.zero_ext(
-            (bits - rhs.bits()) as u32,
+        il::Expression::Zext(bits, ref rhs) => context.try_zero_ext(
+            checked_width(bits.checked_sub(rhs.bits()), expression)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Sext(bits, ref rhs) => context.sign_ext(
-            (bits - rhs.bits()) as u32,
+        )?,
+        il::Expression::Sext(bits, ref rhs) => context.try_sign_ext(
+            checked_width(bits.checked_sub(rhs.bits()), expression)?,
             &expression_to_ast(context, rhs)?,
-        ),
-        il::Expression::Trun(bits, ref rhs) => {
-            context.extract((bits - 1) as u32, 0, &expression_to_ast(context, rhs)?)
-        }
-        il::Expression::Ite(ref cond, ref then, ref else_) => context.ite(
-            &context.eq(
+        )?,
+        il::Expression::Trun(bits, ref rhs) => context.try_extract(
+            checked_width(bits.checked_sub(1), expression)?,
+            0,
+            &expression_to_ast(context, rhs)?,
+        )?,
+        il::Expression::Ite(ref cond, ref then, ref else_) => context.try_ite(
+            &context.try_eq(
                 &expression_to_ast(context, cond)?,
                 &context.mk_numeral(1, &context.mk_bv_sort(1))?,
-            ),
+            )?,
             &expression_to_ast(context, then)?,
             &expression_to_ast(context, else_)?,
-        ),
+        )?,
+    })
+}
+
+/// A width computed for an extension or truncation, or an error if it
+/// underflowed because `expression` is malformed.
+fn checked_width(width: Option<usize>, expression: &il::Expression) -> Result<u32> {
+    width
+        .map(|width| width as u32)
+        .ok_or_else(|| ErrorKind::InvalidWidth(expression.to_string()).into())
+}
+
+fn unsupported(ast: &Ast) -> Error {
+    ErrorKind::UnsupportedAst(ast.to_string().unwrap_or_default()).into()
+}
+
+/// The bitvector of `bits` bits with every bit set.
+fn ones(bits: usize) -> il::Expression {
+    let value = (BigUint::from(1u32) << bits) - BigUint::from(1u32);
+    il::Expression::constant(il::Constant::new_big(value, bits))
+}
+
+fn not(expression: il::Expression) -> Result<il::Expression> {
+    let bits = expression.bits();
+    Ok(il::Expression::xor(expression, ones(bits))?)
+}
+
+/// `lhs` in the high bits and `rhs` in the low bits.
+fn concat(lhs: il::Expression, rhs: il::Expression) -> Result<il::Expression> {
+    let bits = lhs.bits() + rhs.bits();
+    let shift = il::expr_const(rhs.bits() as u64, bits);
+    Ok(il::Expression::or(
+        il::Expression::shl(il::Expression::zext(bits, lhs)?, shift)?,
+        il::Expression::zext(bits, rhs)?,
+    )?)
+}
+
+fn fold_args<F>(context: &Context, ast: &Ast, f: F) -> Result<il::Expression>
+where
+    F: Fn(il::Expression, il::Expression) -> Result<il::Expression>,
+{
+    let mut args = ast.args().into_iter();
+    let first = match args.next() {
+        Some(first) => ast_to_expression(context, &first)?,
+        None => return Err(unsupported(ast)),
+    };
+    args.try_fold(first, |lhs, rhs| f(lhs, ast_to_expression(context, &rhs)?))
+}
+
+fn binary_args(context: &Context, ast: &Ast) -> Result<(il::Expression, il::Expression)> {
+    match (ast.arg(0), ast.arg(1)) {
+        (Some(lhs), Some(rhs)) if ast.num_args() == 2 => Ok((
+            ast_to_expression(context, &lhs)?,
+            ast_to_expression(context, &rhs)?,
+        )),
+        _ => Err(unsupported(ast)),
+    }
+}
+
+fn unary_arg(context: &Context, ast: &Ast) -> Result<il::Expression> {
+    match ast.arg(0) {
+        Some(arg) if ast.num_args() == 1 => ast_to_expression(context, &arg),
+        _ => Err(unsupported(ast)),
+    }
+}
+
+fn int_parameter(ast: &Ast, i: usize) -> Result<usize> {
+    ast.decl()
+        .and_then(|decl| decl.int_parameters().get(i).cloned())
+        .map(|parameter| parameter as usize)
+        .ok_or_else(|| unsupported(ast))
+}
+
+/// Shift `lhs` right by `rhs`, filling with its sign bit. This holds for
+/// shifts of `lhs.bits()` and more, which `il::Expression::ashr` does not.
+fn ashr(lhs: il::Expression, rhs: il::Expression) -> Result<il::Expression> {
+    let bits = lhs.bits();
+    Ok(il::Expression::ite(
+        il::Expression::cmplts(lhs.clone(), il::expr_const(0, bits))?,
+        not(il::Expression::shr(not(lhs.clone())?, rhs.clone())?)?,
+        il::Expression::shr(lhs, rhs)?,
+    )?)
+}
+
+/// Rotate `expression` left by `shift`, which may be any value.
+fn rotate_left(expression: il::Expression, shift: il::Expression) -> Result<il::Expression> {
+    let bits = expression.bits();
+    let shift = il::Expression::modu(shift, il::expr_const(bits as u64, bits))?;
+    Ok(il::Expression::rotl(expression, shift)?)
+}
+
+/// Translate a Z3 term back into falcon IL.
+///
+/// Bitvector terms translate to expressions of the same width, and Boolean
+/// terms to 1-bit expressions which are 1 for true, as `expression_to_ast`
+/// produces. Terms over other theories, Boolean constants, or applications
+/// of uninterpreted functions, return `ErrorKind::UnsupportedAst`.
+pub fn ast_to_expression<'c>(context: &'c Context, ast: &Ast<'c>) -> Result<il::Expression> {
+    use DeclKind;
+
+    let sort = ast.sort();
+    let bits = match sort.bv_size() {
+        Some(bits) => bits,
+        None if sort.is_bool() => 1,
+        None => return Err(unsupported(ast)),
+    };
+
+    let decl_kind = match ast.decl_kind() {
+        Some(decl_kind) => decl_kind,
+        None => return Err(unsupported(ast)),
+    };
+
+    Ok(match decl_kind {
+        DeclKind::True | DeclKind::Bit1 => il::expr_const(1, 1),
+        DeclKind::False | DeclKind::Bit0 => il::expr_const(0, 1),
+        DeclKind::BvNumeral => {
+            let value = ast.as_biguint().ok_or_else(|| unsupported(ast))?;
+            il::Expression::constant(il::Constant::new_big(value, bits))
+        }
+        // A Boolean constant has no 1-bit scalar it could round trip through.
+        DeclKind::Uninterpreted if ast.num_args() == 0 && sort.is_bool() => {
+            return Err(unsupported(ast))
+        }
+        DeclKind::Uninterpreted if ast.num_args() == 0 => {
+            let name = ast.decl().ok_or_else(|| unsupported(ast))?.name();
+            il::expr_scalar(name, bits)
+        }
+
+        DeclKind::Eq | DeclKind::Iff | DeclKind::Bvcomp => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::cmpeq(lhs, rhs)?
+        }
+        DeclKind::Distinct => {
+            let args = ast
+                .args()
+                .iter()
+                .map(|arg| ast_to_expression(context, arg))
+                .collect::<Result<Vec<il::Expression>>>()?;
+            let mut distinct = il::expr_const(1, 1);
+            for (i, lhs) in args.iter().enumerate() {
+                for rhs in &args[i + 1..] {
+                    distinct = il::Expression::and(
+                        distinct,
+                        il::Expression::cmpneq(lhs.clone(), rhs.clone())?,
+                    )?;
+                }
+            }
+            distinct
+        }
+        DeclKind::Ite => match (ast.arg(0), ast.arg(1), ast.arg(2)) {
+            (Some(cond), Some(then), Some(else_)) => il::Expression::ite(
+                ast_to_expression(context, &cond)?,
+                ast_to_expression(context, &then)?,
+                ast_to_expression(context, &else_)?,
+            )?,
+            _ => return Err(unsupported(ast)),
+        },
+        DeclKind::Not | DeclKind::Bvnot => not(unary_arg(context, ast)?)?,
+        DeclKind::Implies => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::or(not(lhs)?, rhs)?
+        }
+
+        DeclKind::And | DeclKind::Bvand => {
+            fold_args(context, ast, |lhs, rhs| Ok(il::Expression::and(lhs, rhs)?))?
+        }
+        DeclKind::Or | DeclKind::Bvor => {
+            fold_args(context, ast, |lhs, rhs| Ok(il::Expression::or(lhs, rhs)?))?
+        }
+        DeclKind::Xor | DeclKind::Bvxor => {
+            fold_args(context, ast, |lhs, rhs| Ok(il::Expression::xor(lhs, rhs)?))?
+        }
+        DeclKind::Bvnand => not(fold_args(context, ast, |lhs, rhs| {
+            Ok(il::Expression::and(lhs, rhs)?)
+        })?)?,
+        DeclKind::Bvnor => not(fold_args(context, ast, |lhs, rhs| {
+            Ok(il::Expression::or(lhs, rhs)?)
+        })?)?,
+        DeclKind::Bvxnor => not(fold_args(context, ast, |lhs, rhs| {
+            Ok(il::Expression::xor(lhs, rhs)?)
+        })?)?,
+
+        DeclKind::Bvneg => il::Expression::sub(il::expr_const(0, bits), unary_arg(context, ast)?)?,
+        DeclKind::Bvadd => fold_args(context, ast, |lhs, rhs| Ok(il::Expression::add(lhs, rhs)?))?,
+        DeclKind::Bvsub => fold_args(context, ast, |lhs, rhs| Ok(il::Expression::sub(lhs, rhs)?))?,
+        DeclKind::Bvmul => fold_args(context, ast, |lhs, rhs| Ok(il::Expression::mul(lhs, rhs)?))?,
+        DeclKind::Bvudiv | DeclKind::BvudivUnchecked => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::divu(lhs, rhs)?
+        }
+        DeclKind::Bvurem | DeclKind::BvuremUnchecked => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::modu(lhs, rhs)?
+        }
+        DeclKind::Bvsdiv | DeclKind::BvsdivUnchecked => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::divs(lhs, rhs)?
+        }
+        DeclKind::Bvsrem | DeclKind::BvsremUnchecked => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::mods(lhs, rhs)?
+        }
+        // The signed modulus takes the sign of the divisor, where `mods`
+        // takes the sign of the dividend. They differ by the divisor when
+        // the signs differ and the remainder is not zero.
+        DeclKind::Bvsmod | DeclKind::BvsmodUnchecked => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            let bits = lhs.bits();
+            let remainder = il::Expression::mods(lhs.clone(), rhs.clone())?;
+            let fix_up = il::Expression::and(
+                il::Expression::cmplts(
+                    il::Expression::xor(lhs, rhs.clone())?,
+                    il::expr_const(0, bits),
+                )?,
+                il::Expression::cmpneq(remainder.clone(), il::expr_const(0, bits))?,
+            )?;
+            il::Expression::ite(
+                fix_up,
+                il::Expression::add(remainder.clone(), rhs)?,
+                remainder,
+            )?
+        }
+
+        DeclKind::Bvshl => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::shl(lhs, rhs)?
+        }
+        DeclKind::Bvlshr => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::shr(lhs, rhs)?
+        }
+        DeclKind::Bvashr => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            ashr(lhs, rhs)?
+        }
+        DeclKind::RotateLeft => {
+            let shift = il::expr_const(int_parameter(ast, 0)? as u64, bits);
+            rotate_left(unary_arg(context, ast)?, shift)?
+        }
+        DeclKind::RotateRight => {
+            let shift = (bits - int_parameter(ast, 0)? % bits) as u64;
+            rotate_left(unary_arg(context, ast)?, il::expr_const(shift, bits))?
+        }
+        DeclKind::RotateLeftExt => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            rotate_left(lhs, rhs)?
+        }
+        DeclKind::RotateRightExt => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            let rhs = il::Expression::modu(rhs, il::expr_const(bits as u64, bits))?;
+            rotate_left(
+                lhs,
+                il::Expression::sub(il::expr_const(bits as u64, bits), rhs)?,
+            )?
+        }
+
+        DeclKind::Bvult => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::cmpltu(lhs, rhs)?
+        }
+        DeclKind::Bvslt => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::cmplts(lhs, rhs)?
+        }
+        DeclKind::Bvugt => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::cmpltu(rhs, lhs)?
+        }
+        DeclKind::Bvsgt => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            il::Expression::cmplts(rhs, lhs)?
+        }
+        DeclKind::Bvule => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            not(il::Expression::cmpltu(rhs, lhs)?)?
+        }
+        DeclKind::Bvsle => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            not(il::Expression::cmplts(rhs, lhs)?)?
+        }
+        DeclKind::Bvuge => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            not(il::Expression::cmpltu(lhs, rhs)?)?
+        }
+        DeclKind::Bvsge => {
+            let (lhs, rhs) = binary_args(context, ast)?;
+            not(il::Expression::cmplts(lhs, rhs)?)?
+        }
+
+        DeclKind::Bvredor => {
+            let arg = unary_arg(context, ast)?;
+            let bits = arg.bits();
+            il::Expression::cmpneq(arg, il::expr_const(0, bits))?
+        }
+        DeclKind::Bvredand => {
+            let arg = unary_arg(context, ast)?;
+            let bits = arg.bits();
+            il::Expression::cmpeq(arg, ones(bits))?
+        }
+
+        DeclKind::Concat => fold_args(context, ast, concat)?,
+        DeclKind::Repeat => {
+            let arg = unary_arg(context, ast)?;
+            let mut repeat = arg.clone();
+            for _ in 1..int_parameter(ast, 0)? {
+                repeat = concat(repeat, arg.clone())?;
+            }
+            repeat
+        }
+        DeclKind::Extract => {
+            let (high, low) = (int_parameter(ast, 0)?, int_parameter(ast, 1)?);
+            let arg = unary_arg(context, ast)?;
+            let arg_bits = arg.bits();
+            let arg = if low > 0 {
+                il::Expression::shr(arg, il::expr_const(low as u64, arg_bits))?
+            } else {
+                arg
+            };
+            if high - low + 1 < arg_bits {
+                il::Expression::trun(high - low + 1, arg)?
+            } else {
+                arg
+            }
+        }
+        DeclKind::ZeroExt | DeclKind::SignExt => {
+            let arg = unary_arg(context, ast)?;
+            if int_parameter(ast, 0)? == 0 {
+                arg
+            } else if decl_kind == DeclKind::ZeroExt {
+                il::Expression::zext(bits, arg)?
+            } else {
+                il::Expression::sext(bits, arg)?
+            }
+        }
+
+        _ => return Err(unsupported(ast)),
+    })
+}
+
+/// Simplify `expression` with Z3's simplifier. Returns `expression` itself
+/// if Z3's result is no smaller, as when Z3 rewrites a subtraction into an
+/// addition and a multiplication.
+pub fn simplify(expression: &il::Expression) -> Result<il::Expression> {
+    simplify_with_params(expression, |_| Ok(()))
+}
+
+/// Simplify `expression` with Z3's simplifier, configured by `set_params`,
+/// for example with `params.set_bool("elim_sign_ext", false)`. Like
+/// `simplify`, returns `expression` itself if the result is no smaller.
+pub fn simplify_with_params<F>(expression: &il::Expression, set_params: F) -> Result<il::Expression>
+where
+    F: FnOnce(&Params) -> Result<()>,
+{
+    let context = Context::new(Config::new());
+    let params = Params::new(&context);
+    set_params(&params)?;
+
+    let ast = expression_to_ast(&context, expression)?;
+    let simplified = context.try_simplify_with_params(&ast, &params)?;
+    let simplified = ast_to_expression(&context, &simplified)?;
+    Ok(if size(&simplified) < size(expression) {
+        simplified
+    } else {
+        expression.clone()
     })
 }
 
+/// The number of nodes in `expression`.
+fn size(expression: &il::Expression) -> usize {
+    match *expression {
+        il::Expression::Scalar(_) | il::Expression::Constant(_) => 1,
+        il::Expression::Add(ref lhs, ref rhs)
+        | il::Expression::Sub(ref lhs, ref rhs)
+        | il::Expression::Mul(ref lhs, ref rhs)
+        | il::Expression::Divu(ref lhs, ref rhs)
+        | il::Expression::Modu(ref lhs, ref rhs)
+        | il::Expression::Divs(ref lhs, ref rhs)
+        | il::Expression::Mods(ref lhs, ref rhs)
+        | il::Expression::And(ref lhs, ref rhs)
+        | il::Expression::Or(ref lhs, ref rhs)
+        | il::Expression::Xor(ref lhs, ref rhs)
+        | il::Expression::Shl(ref lhs, ref rhs)
+        | il::Expression::Shr(ref lhs, ref rhs)
+        | il::Expression::Cmpeq(ref lhs, ref rhs)
+        | il::Expression::Cmpneq(ref lhs, ref rhs)
+        | il::Expression::Cmplts(ref lhs, ref rhs)
+        | il::Expression::Cmpltu(ref lhs, ref rhs) => 1 + size(lhs) + size(rhs),
+        il::Expression::Zext(_, ref rhs)
+        | il::Expression::Sext(_, ref rhs)
+        | il::Expression::Trun(_, ref rhs) => 1 + size(rhs),
+        il::Expression::Ite(ref cond, ref then, ref else_) => {
+            1 + size(cond) + size(then) + size(else_)
+        }
+    }
+}
+
 #[test]
 fn test_solve() {
     let expression = il::expr_const(32, 32);
@@ -402,3 +1266,415 @@ fn test_minimize() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_malformed_expression() {
+    // Falcon checks sorts when building expressions, so build mismatched
+    // expressions directly.
+    let a = || Box::new(il::expr_scalar("a", 32));
+    let b = || Box::new(il::expr_scalar("b", 64));
+    let expressions = vec![
+        il::Expression::Add(a(), b()),
+        il::Expression::Cmpeq(a(), b()),
+        il::Expression::Cmpneq(a(), b()),
+        il::Expression::Cmplts(a(), b()),
+        il::Expression::Cmpltu(a(), b()),
+        il::Expression::Ite(a(), a(), a()),
+        il::Expression::Trun(64, a()),
+    ];
+
+    let context = Context::new(Config::new());
+    for expression in &expressions {
+        match expression_to_ast(&context, expression) {
+            Err(Error(ErrorKind::Z3(..), _)) => {}
+            _ => panic!("expected a Z3 error for {}", expression),
+        }
+    }
+
+    // Extensions to fewer bits and truncations to none never reach Z3.
+    let widths = vec![
+        il::Expression::Zext(32, b()),
+        il::Expression::Sext(32, b()),
+        il::Expression::Trun(0, a()),
+    ];
+    for expression in &widths {
+        match expression_to_ast(&context, expression) {
+            Err(Error(ErrorKind::InvalidWidth(_), _)) => {}
+            _ => panic!("expected ErrorKind::InvalidWidth for {}", expression),
+        }
+    }
+
+    assert!(solve(&[], &expressions[0]).is_err());
+}
+
+#[test]
+fn test_find_overflows() -> Result<()> {
+    let rdx = il::expr_scalar("rdx", 64);
+    let rax = il::expr_scalar("rax", 64);
+
+    // rdx < 0x10, so rdx + 1 can never overflow, but rax * 2 can.
+    let constraint = il::Expression::cmpltu(rdx.clone(), il::expr_const(0x10, 64))?;
+    let expression = il::Expression::add(
+        il::Expression::add(rdx.clone(), il::expr_const(1, 64))?,
+        il::Expression::mul(rax.clone(), il::expr_const(2, 64))?,
+    )?;
+
+    let overflows = find_overflows(
+        &[constraint],
+        &il::Expression::add(rdx, il::expr_const(1, 64))?,
+    )?;
+    assert!(overflows.is_empty());
+
+    let overflows = find_overflows(&[], &expression)?;
+    let mul = il::Expression::mul(rax.clone(), il::expr_const(2, 64))?;
+    let unsigned = overflows
+        .iter()
+        .find(|overflow| overflow.expression() == &mul && overflow.kind() == OverflowKind::Unsigned)
+        .unwrap();
+    let rax_value = &unsigned.witness()[rax.get_scalar().unwrap()];
+    assert!(rax_value.value_u64().unwrap() >= 0x8000_0000_0000_0000);
+
+    // A repeated subterm is only reported once.
+    let overflows = find_overflows(&[], &il::Expression::xor(mul.clone(), mul.clone())?)?;
+    assert_eq!(
+        overflows
+            .iter()
+            .filter(|overflow| overflow.kind() == OverflowKind::Unsigned)
+            .count(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_wide_constants() -> Result<()> {
+    for &bits in &[65usize, 80, 128, 129] {
+        let value = (BigUint::from(1u64) << (bits - 1)) + BigUint::from(0x1234u64);
+        let expression = il::Expression::constant(il::Constant::new_big(value.clone(), bits));
+
+        let constant = solve(&[], &expression)?.unwrap();
+        assert_eq!(constant.bits(), bits);
+        assert_eq!(constant.value(), &value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_ast_to_expression() -> Result<()> {
+    use Sort;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    // Every expression must translate back to one which Z3 proves equal.
+    let equivalent = |ast: &Ast, expression: &il::Expression| -> Result<bool> {
+        let ast = if ast.sort().is_bool() {
+            let sort = context.mk_bv_sort(1);
+            context.try_ite(
+                ast,
+                &context.mk_numeral(1, &sort)?,
+                &context.mk_numeral(0, &sort)?,
+            )?
+        } else {
+            ast.clone()
+        };
+        solver.push();
+        solver.assert(
+            &context.try_not(&context.try_eq(&ast, &expression_to_ast(&context, expression)?)?)?,
+        );
+        let check = solver.check();
+        solver.pop(1)?;
+        Ok(check == Check::Unsat)
+    };
+
+    let sort8 = context.mk_bv_sort(8);
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let b = context.mk_var("b", &sort32)?;
+    let c = context.mk_var("c", &sort8)?;
+    let d = context.mk_var("d", &sort8)?;
+
+    let asts = vec![
+        context.bvadd(&a, &context.bvmul(&b, &context.mk_numeral(3, &sort32)?)),
+        context.bvsub(&context.bvneg(&a), &context.bvnot(&b)),
+        context.bvudiv(&a, &b),
+        context.bvsrem(&a, &b),
+        // The signed modulus is slow to prove equivalent at 32 bits.
+        context.bvsmod(&c, &d),
+        context.bvsmod(&c, &context.mk_numeral(0xf9, &sort8)?),
+        context.simplify(&context.bvsmod(&c, &d)),
+        context.bvxnor(&context.bvnand(&a, &b), &context.bvnor(&a, &b)),
+        context.bvshl(&a, &b),
+        context.bvlshr(&a, &b),
+        context.bvashr(&a, &b),
+        context.rotate_left(5, &a),
+        context.rotate_right(5, &a),
+        context.rotate_left_ext(&a, &b),
+        context.rotate_right_ext(&a, &b),
+        context.extract(19, 4, &a),
+        context.extract(31, 24, &a),
+        context.concat(&c, &context.extract(15, 0, &b)),
+        context.repeat(3, &c),
+        context.zero_ext(24, &c),
+        context.sign_ext(24, &c),
+        context.ite(&context.bvult(&a, &b), &a, &b),
+        context.bvule(&a, &b),
+        context.bvsge(&a, &b),
+        context.bvsgt(&a, &b),
+        context.implies(&context.bvslt(&a, &b), &context.distinct(&[&a, &b])),
+        context.bvredor(&c),
+        context.bvredand(&c),
+    ];
+    for ast in &asts {
+        let expression = ast_to_expression(&context, ast)?;
+        assert!(equivalent(ast, &expression)?, "{:?}", ast.to_string());
+    }
+
+    let expression = ast_to_expression(&context, &context.extract(7, 0, &a))?;
+    assert_eq!(
+        expression,
+        il::Expression::trun(8, il::expr_scalar("a", 32))?
+    );
+
+    let array_sort = Sort::array(&sort32, &sort8);
+    let array = context.mk_var("mem", &array_sort)?;
+    match ast_to_expression(&context, &context.select(&array, &a)) {
+        Err(Error(ErrorKind::UnsupportedAst(_), _)) => {}
+        _ => panic!("expected ErrorKind::UnsupportedAst"),
+    }
+
+    let flag = context.mk_bool_var("flag")?;
+    match ast_to_expression(&context, &context.and(&[&flag, &context.bvult(&a, &b)])) {
+        Err(Error(ErrorKind::UnsupportedAst(_), _)) => {}
+        _ => panic!("expected ErrorKind::UnsupportedAst"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_simplify() -> Result<()> {
+    let a = il::expr_scalar("a", 32);
+    let b = il::expr_scalar("b", 32);
+
+    // A flag computation of the kind lifters produce, which is just a == b.
+    let zf = il::Expression::ite(
+        il::Expression::cmpeq(
+            il::Expression::sub(a.clone(), b.clone())?,
+            il::expr_const(0, 32),
+        )?,
+        il::expr_const(1, 1),
+        il::expr_const(0, 1),
+    )?;
+    let simplified = simplify(&zf)?;
+    assert_eq!(simplified.bits(), 1);
+    assert!(simplified.to_string().len() < zf.to_string().len());
+
+    let expression = il::Expression::add(
+        il::Expression::mul(a.clone(), il::expr_const(0, 32))?,
+        il::Expression::xor(b.clone(), il::expr_const(0, 32))?,
+    )?;
+    assert_eq!(simplify(&expression)?, b);
+
+    let expression = il::Expression::trun(
+        8,
+        il::Expression::sext(64, il::Expression::trun(16, a.clone())?)?,
+    )?;
+    assert_eq!(simplify(&expression)?, il::Expression::trun(8, a.clone())?);
+
+    // By default Z3 expands the sign extension into a larger term, so the
+    // original is kept. Turning off elim_sign_ext keeps the sign extension
+    // and just drops the addition.
+    let sext = il::Expression::sext(64, a.clone())?;
+    let expression = il::Expression::add(sext.clone(), il::expr_const(0, 64))?;
+    assert_eq!(
+        simplify_with_params(&expression, |params| params
+            .set_bool("elim_sign_ext", false))?,
+        sext
+    );
+    assert_eq!(simplify(&expression)?, expression);
+
+    assert!(simplify_with_params(&expression, |params| params
+        .set_bool("not_a_parameter", true))
+    .is_err());
+
+    // Z3 rewrites subtraction into a larger addition and multiplication, so
+    // the original is kept.
+    let expression = il::Expression::sub(
+        il::Expression::sub(a.clone(), b.clone())?,
+        il::Expression::sub(b.clone(), il::expr_scalar("c", 32))?,
+    )?;
+    assert_eq!(simplify(&expression)?, expression);
+
+    Ok(())
+}
+
+#[test]
+fn test_solve_scalars() -> Result<()> {
+    let a = il::expr_scalar("a", 32);
+    let b = il::expr_scalar("b", 8);
+    let constraints = vec![
+        il::Expression::cmpeq(
+            il::Expression::add(a.clone(), il::expr_const(1, 32))?,
+            il::expr_const(0x1000, 32),
+        )?,
+        il::Expression::cmpltu(il::expr_const(0xfe, 8), b.clone())?,
+    ];
+
+    let scalars = solve_scalars(&constraints)?.unwrap();
+    assert_eq!(scalars.len(), 2);
+    assert_eq!(scalars[&il::scalar("a", 32)], il::const_(0xfff, 32));
+    assert_eq!(scalars[&il::scalar("b", 8)], il::const_(0xff, 8));
+
+    // a & 0 == 0 holds for any a, but a still gets a value.
+    let constraints = vec![
+        il::Expression::cmpeq(
+            il::Expression::and(a.clone(), il::expr_const(0, 32))?,
+            il::expr_const(0, 32),
+        )?,
+        il::Expression::cmpeq(b.clone(), il::expr_const(5, 8))?,
+    ];
+    let scalars = solve_scalars(&constraints)?.unwrap();
+    assert_eq!(scalars.len(), 2);
+    assert!(scalars.contains_key(&il::scalar("a", 32)));
+    assert_eq!(scalars[&il::scalar("b", 8)], il::const_(5, 8));
+
+    let constraints = vec![il::Expression::cmpltu(b.clone(), il::expr_const(0, 8))?];
+    assert!(solve_scalars(&constraints)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_query_to_smt2() -> Result<()> {
+    let a = il::expr_scalar("a", 32);
+    let constraints = vec![il::Expression::cmpeq(a.clone(), il::expr_const(7, 32))?];
+    let value = il::Expression::add(a, il::expr_const(1, 32))?;
+
+    let smt2 = query_to_smt2(&constraints, &value)?;
+    assert!(smt2.contains("(declare-fun a () (_ BitVec 32))"));
+    assert!(smt2.contains("(declare-fun SOLVER_RESULT () (_ BitVec 32))"));
+    assert!(smt2.ends_with("(check-sat)\n(get-value (SOLVER_RESULT))\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_optimize_anytime() -> Result<()> {
+    let rax = il::expr_scalar("rax", 64);
+    let constraints = vec![
+        il::Expression::cmpltu(rax.clone(), il::expr_const(0x7fff_0000_1000, 64))?,
+        il::Expression::cmpltu(il::expr_const(0x4000_0000, 64), rax.clone())?,
+        il::Expression::cmpeq(
+            il::Expression::and(rax.clone(), il::expr_const(0xf, 64))?,
+            il::expr_const(8, 64),
+        )?,
+    ];
+
+    let maximum = maximize_anytime(&constraints, &rax, 10_000)?.unwrap();
+    assert!(maximum.is_optimal());
+    assert_eq!(maximum.value(), &il::const_(0x7fff_0000_0ff8, 64));
+
+    let minimum = minimize_anytime(&constraints, &rax, 10_000)?.unwrap();
+    assert!(minimum.is_optimal());
+    assert_eq!(minimum.value(), &il::const_(0x4000_0008, 64));
+
+    // The fallback search finds the same optimums on its own.
+    let context = Context::new(Config::new().enable_model());
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let maximum = bitwise_search(&context, &constraints, &rax, true, deadline, None)?.unwrap();
+    assert!(maximum.is_optimal());
+    assert_eq!(maximum.value(), &il::const_(0x7fff_0000_0ff8, 64));
+    let minimum = bitwise_search(&context, &constraints, &rax, false, deadline, None)?.unwrap();
+    assert!(minimum.is_optimal());
+    assert_eq!(minimum.value(), &il::const_(0x4000_0008, 64));
+
+    // With no time at all, the search finds no value.
+    let deadline = Instant::now();
+    assert!(bitwise_search(&context, &constraints, &rax, true, deadline, None)?.is_none());
+
+    // With no time but a seed, the search keeps the seed.
+    let seed = BigUint::from(0x4000_0008u64);
+    let maximum =
+        bitwise_search(&context, &constraints, &rax, true, deadline, Some(seed))?.unwrap();
+    assert!(!maximum.is_optimal());
+    assert_eq!(maximum.value(), &il::const_(0x4000_0008, 64));
+
+    // A millisecond is not enough to get stuck in the Optimize engine.
+    if let Some(result) = maximize_anytime(&constraints, &rax, 1)? {
+        assert!(result.value().value() <= result.bound().value());
+    }
+
+    let unsat = vec![il::Expression::cmpltu(rax.clone(), il::expr_const(0, 64))?];
+    assert!(maximize_anytime(&unsat, &rax, 10_000)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_optimize_anytime_partial() -> Result<()> {
+    // x is either `fallback` or a factor of a 62-bit semiprime, so any value
+    // better than `fallback` needs a factorization.
+    let x = il::expr_scalar("x", 32);
+    let p = il::expr_scalar("p", 32);
+    let q = il::expr_scalar("q", 32);
+    let factors = il::Expression::and(
+        il::Expression::cmpeq(
+            il::Expression::mul(
+                il::Expression::zext(64, p.clone())?,
+                il::Expression::zext(64, q.clone())?,
+            )?,
+            il::expr_const(0x2004_0003_4030_0015, 64),
+        )?,
+        il::Expression::and(
+            il::Expression::cmpltu(il::expr_const(1, 32), p.clone())?,
+            il::Expression::cmpltu(il::expr_const(1, 32), q)?,
+        )?,
+    )?;
+    let constraints = |fallback: u64| -> Result<Vec<il::Expression>> {
+        Ok(vec![il::Expression::or(
+            il::Expression::cmpeq(x.clone(), il::expr_const(fallback, 32))?,
+            il::Expression::and(
+                il::Expression::cmpeq(x.clone(), p.clone())?,
+                factors.clone(),
+            )?,
+        )?])
+    };
+
+    // The factors are 0x40000003 and 0x80100007, so the search runs out of
+    // resources well before it finds either. A resource limit, unlike the
+    // deadline, stops the search at the same point on every machine. Seed
+    // the search with the fallback, which the solver is slow to find on its
+    // own.
+    let context = Context::new(Config::new().enable_model().set("rlimit", "200000")?);
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let maximum = bitwise_search(
+        &context,
+        &constraints(1)?,
+        &x,
+        true,
+        deadline,
+        Some(BigUint::from(1u32)),
+    )?
+    .unwrap();
+    assert!(!maximum.is_optimal());
+    assert!(maximum.value().value_u64().unwrap() <= 0x8010_0007);
+    assert!(0x8010_0007 <= maximum.bound().value_u64().unwrap());
+
+    let minimum = bitwise_search(
+        &context,
+        &constraints(0xffff_ffff)?,
+        &x,
+        false,
+        deadline,
+        Some(BigUint::from(0xffff_ffffu32)),
+    )?
+    .unwrap();
+    assert!(!minimum.is_optimal());
+    assert!(minimum.bound().value_u64().unwrap() <= 0x4000_0003);
+    assert!(0x4000_0003 <= minimum.value().value_u64().unwrap());
+
+    Ok(())
+}
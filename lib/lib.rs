@@ -8,19 +8,33 @@ extern crate z3_sys;
 mod ast;
 mod config;
 mod context;
+mod decl_kind;
+mod func_decl;
+mod goal;
 pub mod il;
 mod model;
 mod optimize;
+mod params;
+mod proof;
 mod solver;
 mod sort;
+mod statistics;
+mod tactic;
 
-pub use self::ast::Ast;
+pub use self::ast::{Ast, AstKind};
 pub use self::config::Config;
-pub use self::context::Context;
-pub use self::model::Model;
-pub use self::optimize::Optimize;
+pub use self::context::{Context, InterruptHandle};
+pub use self::decl_kind::{DeclKind, ProofRule};
+pub use self::func_decl::FuncDecl;
+pub use self::goal::Goal;
+pub use self::model::{ArrayInterp, FuncInterp, Model};
+pub use self::optimize::{Objective, Optimize, Priority};
+pub use self::params::Params;
+pub use self::proof::ProofStep;
 pub use self::solver::{Check, Solver};
 pub use self::sort::Sort;
+pub use self::statistics::{Statistics, StatisticsValue};
+pub use self::tactic::{ApplyResult, Probe, Tactic};
 
 pub mod error {
     error_chain! {
@@ -31,6 +45,24 @@ pub mod error {
             Falcon(::falcon::error::Error);
             NulError(::std::ffi::NulError);
         }
+        errors {
+            Z3(code: ::z3_sys::ErrorCode, message: String) {
+                description("Z3 error")
+                display("Z3 error {:?}: {}", code, message)
+            }
+            UnsupportedAst(ast: String) {
+                description("Z3 term has no falcon IL equivalent")
+                display("Z3 term has no falcon IL equivalent: {}", ast)
+            }
+            ContextMismatch {
+                description("Z3 object belongs to a different context")
+                display("Z3 object belongs to a different context")
+            }
+            InvalidWidth(expression: String) {
+                description("falcon IL expression has an invalid width")
+                display("falcon IL expression has an invalid width: {}", expression)
+            }
+        }
     }
 }
 
@@ -53,7 +85,7 @@ fn test() {
     let model = Model::new(&context, &solver).unwrap();
     let b_value = model.get_const_interp(&b).unwrap();
 
-    println!("b: {:?}", b_value.to_string(&context));
+    println!("b: {:?}", b_value.to_string());
 }
 
 #[cfg(test)]
@@ -102,3 +134,202 @@ fn rdx() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn ast_clone_outlives_original() {
+    let context = Context::new(Config::new());
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32).unwrap();
+    let sum = context.bvadd(&a, &context.mk_numeral(1, &sort32).unwrap());
+
+    let sum_clone = sum.clone();
+    drop(sum);
+    drop(sort32);
+
+    assert_eq!(sum_clone.to_string().unwrap(), "(bvadd a #x00000001)");
+}
+
+#[test]
+fn mixed_contexts() {
+    // Lifetimes cannot keep two contexts in one scope apart, so mixing them
+    // is an error at runtime.
+    let c1 = Context::new(Config::new());
+    let c2 = Context::new(Config::new());
+    let a = c1.mk_var("a", &c1.mk_bv_sort(32)).unwrap();
+    let b = c2.mk_var("b", &c2.mk_bv_sort(32)).unwrap();
+
+    match c1.try_bvadd(&a, &b) {
+        Err(Error(ErrorKind::ContextMismatch, _)) => {}
+        _ => panic!("expected ErrorKind::ContextMismatch"),
+    }
+    assert!(c1.mk_var("c", &c2.mk_bv_sort(32)).is_err());
+    assert!(Sort::try_array(&c1.mk_bv_sort(32), &c2.mk_bv_sort(8)).is_err());
+
+    let solver = Solver::new(&c1);
+    assert!(solver.try_assert(&c2.mk_true()).is_err());
+    assert!(solver.assertions().is_empty());
+}
+
+#[test]
+fn boolean_connectives() {
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+
+    let a = context.mk_bool_var("a").unwrap();
+    let b = context.mk_bool_var("b").unwrap();
+
+    solver.assert(&context.and(&[&context.or(&[&a, &b]), &context.not(&a)]));
+    solver.assert(&context.implies(&b, &context.mk_true()));
+    solver.assert(&context.iff(&context.xor(&a, &b), &context.mk_true()));
+
+    let model = Model::new(&context, &solver).unwrap();
+    let b_value = model.get_const_interp(&b).unwrap();
+    assert_eq!(b_value.to_string().unwrap(), "true");
+
+    let sort1 = context.mk_bv_sort(1);
+    let x = context.mk_var("x", &sort1).unwrap();
+    let y = context.mk_var("y", &sort1).unwrap();
+    let z = context.mk_var("z", &sort1).unwrap();
+
+    solver.assert(&context.distinct(&[&x, &y, &z]));
+    assert_eq!(solver.check(), Check::Unsat);
+}
+
+#[test]
+fn array_model() {
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let sort8 = context.mk_bv_sort(8);
+    let memory = context
+        .mk_var("memory", &Sort::array(&sort32, &sort8))
+        .unwrap();
+
+    let address0 = context.mk_numeral(0x10, &sort32).unwrap();
+    let address1 = context.mk_numeral(0x20, &sort32).unwrap();
+    let value0 = context.mk_numeral(0x41, &sort8).unwrap();
+    let value1 = context.mk_numeral(0x42, &sort8).unwrap();
+
+    solver.assert(&context.eq(&context.select(&memory, &address0), &value0));
+    solver.assert(&context.eq(&context.select(&memory, &address1), &value1));
+
+    let model = Model::new(&context, &solver).unwrap();
+    let interp = model.get_array_interp(&memory).unwrap();
+
+    let lookup = |address: u64| {
+        interp
+            .entries()
+            .iter()
+            .find(|entry| entry.0.as_biguint().unwrap() == address.into())
+            .map(|entry| &entry.1)
+            .unwrap_or_else(|| interp.default())
+            .as_biguint()
+            .unwrap()
+    };
+
+    assert_eq!(lookup(0x10), 0x41u64.into());
+    assert_eq!(lookup(0x20), 0x42u64.into());
+}
+
+#[test]
+fn uninterpreted_function() {
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let sort64 = context.mk_bv_sort(64);
+    let rdtsc = context
+        .mk_func_decl("rdtsc", &[&sort32, &sort32], &sort64)
+        .unwrap();
+
+    assert_eq!(rdtsc.name(), "rdtsc");
+    assert_eq!(rdtsc.arity(), 2);
+    assert!(rdtsc.domain(1).is_some());
+    assert!(rdtsc.domain(2).is_none());
+
+    let one = context.mk_numeral(1, &sort32).unwrap();
+    let two = context.mk_numeral(2, &sort32).unwrap();
+    let value = context.mk_numeral(0x1234, &sort64).unwrap();
+
+    assert!(rdtsc.try_apply(&[&one]).is_err());
+
+    solver.assert(&context.eq(&rdtsc.apply(&[&one, &two]), &value));
+
+    let model = Model::new(&context, &solver).unwrap();
+    let interp = model.get_func_interp(&rdtsc).unwrap();
+
+    let entry = interp
+        .entries()
+        .iter()
+        .find(|entry| entry.0[0].as_biguint() == Some(1u64.into()))
+        .map(|entry| &entry.1)
+        .or_else(|| interp.else_value())
+        .unwrap();
+    assert_eq!(entry.as_biguint(), Some(0x1234u64.into()));
+}
+
+#[test]
+fn quantifiers() {
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let x = context.mk_var("x", &sort32).unwrap();
+    let y = context.mk_var("y", &sort32).unwrap();
+    let zero = context.mk_numeral(0, &sort32).unwrap();
+
+    // The only x for which x & y is 0 for every y is 0.
+    solver.assert(&context.forall(&[&y], &context.eq(&context.bvand(&x, &y), &zero)));
+
+    let model = Model::new(&context, &solver).unwrap();
+    let x_value = model.get_const_interp(&x).unwrap();
+    assert_eq!(x_value.as_biguint(), Some(0u64.into()));
+
+    // No y doubles to an odd number.
+    let two = context.mk_numeral(2, &sort32).unwrap();
+    let one = context.mk_numeral(1, &sort32).unwrap();
+    let double = context.bvmul(&y, &two);
+    let odd_double = context.exists_with_patterns(&[&y], &[&[&double]], &context.eq(&double, &one));
+
+    solver.assert(&odd_double);
+    assert_eq!(solver.check(), Check::Unsat);
+}
+
+#[test]
+fn config_parameters() {
+    let config = Config::new()
+        .enable_model()
+        .proof(true)
+        .unsat_core(true)
+        .timeout(10_000)
+        .well_sorted_check(true)
+        .auto_config(true)
+        .set("rlimit", "0")
+        .unwrap();
+    let context = Context::new(config);
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32).unwrap();
+    let seven = context.mk_numeral(7, &sort32).unwrap();
+    let tracker = context.mk_bool_var("tracker").unwrap();
+    solver.assert_and_track(&context.bvult(&a, &seven), &tracker);
+    solver.assert(&context.bvugt(&a, &seven));
+    assert_eq!(solver.check(), Check::Unsat);
+    assert_eq!(solver.unsat_core().len(), 1);
+
+    // Set a global parameter to its default, as tests share global state.
+    Config::set_global("verbose", "0").unwrap();
+    assert_eq!(
+        Config::get_global("verbose").unwrap(),
+        Some("0".to_string())
+    );
+
+    // A NUL byte in a caller's string is an error rather than a panic.
+    assert!(Config::new().set("rlimit\0", "0").is_err());
+    assert!(Config::new().trace_file_name("trace\0").is_err());
+    assert!(Config::set_global("verbose", "\0").is_err());
+    assert!(Config::get_global("verbose\0").is_err());
+}
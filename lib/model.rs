@@ -1,11 +1,53 @@
+use falcon::il;
+use std::collections::HashMap;
 use std::ptr::null_mut;
 use z3_sys;
 use Ast;
 use Check;
 use Context;
+use FuncDecl;
 use Optimize;
 use Solver;
 
+/// The interpretation of an array in a `Model`, as the values at particular
+/// indices over a default value.
+pub struct ArrayInterp<'c> {
+    entries: Vec<(Ast<'c>, Ast<'c>)>,
+    default: Ast<'c>,
+}
+
+impl<'c> ArrayInterp<'c> {
+    /// `(index, value)` pairs which differ from the default value.
+    pub fn entries(&self) -> &[(Ast<'c>, Ast<'c>)] {
+        &self.entries
+    }
+
+    /// The value at every index not in `entries`.
+    pub fn default(&self) -> &Ast<'c> {
+        &self.default
+    }
+}
+
+/// The interpretation of a function in a `Model`, as the values for
+/// particular arguments over an else value.
+pub struct FuncInterp<'c> {
+    entries: Vec<(Vec<Ast<'c>>, Ast<'c>)>,
+    else_value: Option<Ast<'c>>,
+}
+
+impl<'c> FuncInterp<'c> {
+    /// `(arguments, value)` pairs which differ from the else value.
+    pub fn entries(&self) -> &[(Vec<Ast<'c>>, Ast<'c>)] {
+        &self.entries
+    }
+
+    /// The value for all arguments not in `entries`, or `None` if the model
+    /// leaves it unspecified.
+    pub fn else_value(&self) -> Option<&Ast<'c>> {
+        self.else_value.as_ref()
+    }
+}
+
 pub struct Model<'c> {
     pub(crate) model: z3_sys::Z3_model,
     context: &'c Context,
@@ -16,25 +58,46 @@ impl<'c> Model<'c> {
         if solver.check() != Check::Sat {
             None
         } else {
-            let m = unsafe { z3_sys::Z3_solver_get_model(context.context, solver.solver) };
-            let model = Model { model: m, context };
-            model.inc_ref();
-            Some(model)
+            Model::from_last_check(context, solver)
+        }
+    }
+
+    /// The model of the last check of `solver`, which must have been sat.
+    pub(crate) fn from_last_check(context: &'c Context, solver: &Solver) -> Option<Model<'c>> {
+        let m = unsafe { z3_sys::Z3_solver_get_model(context.context, solver.solver) };
+        if context.check_error().is_err() || m.is_null() {
+            return None;
         }
+        let model = Model { model: m, context };
+        model.inc_ref();
+        Some(model)
     }
 
     pub fn new_optimize(context: &'c Context, optimize: &Optimize) -> Option<Model<'c>> {
         if optimize.check() != Check::Sat {
             None
         } else {
-            let m = unsafe { z3_sys::Z3_optimize_get_model(context.context, optimize.optimize) };
-            let model = Model { model: m, context };
-            model.inc_ref();
-            Some(model)
+            Model::from_last_optimize(context, optimize)
         }
     }
 
-    pub fn get_const_interp(&self, t: &Ast) -> Option<Ast> {
+    /// The model of the last check of `optimize`. After an unknown check,
+    /// this is the best model found, which may be empty.
+    pub(crate) fn from_last_optimize(
+        context: &'c Context,
+        optimize: &Optimize,
+    ) -> Option<Model<'c>> {
+        let m = unsafe { z3_sys::Z3_optimize_get_model(context.context, optimize.optimize) };
+        if context.check_error().is_err() || m.is_null() {
+            return None;
+        }
+        let model = Model { model: m, context };
+        model.inc_ref();
+        Some(model)
+    }
+
+    pub fn get_const_interp(&self, t: &Ast<'c>) -> Option<Ast<'c>> {
+        self.context.check_context(t).ok()?;
         let mut ast: z3_sys::Z3_ast = null_mut();
         let r = unsafe {
             z3_sys::Z3_model_eval(
@@ -46,12 +109,153 @@ impl<'c> Model<'c> {
             )
         };
         if r {
-            Some(Ast { ast })
+            Some(Ast::new(self.context, ast))
         } else {
             None
         }
     }
 
+    /// The number of constants this model interprets.
+    pub fn num_consts(&self) -> usize {
+        unsafe { z3_sys::Z3_model_get_num_consts(self.context.context, self.model) as usize }
+    }
+
+    /// The declaration of the `i`th constant this model interprets.
+    pub fn get_const_decl(&self, i: usize) -> Option<FuncDecl<'c>> {
+        if i >= self.num_consts() {
+            return None;
+        }
+        Some(FuncDecl::new(self.context, unsafe {
+            z3_sys::Z3_model_get_const_decl(self.context.context, self.model, i as u32)
+        }))
+    }
+
+    /// Every constant this model interprets, with its value.
+    pub fn consts(&self) -> Vec<(FuncDecl<'c>, Ast<'c>)> {
+        (0..self.num_consts())
+            .filter_map(|i| {
+                let decl = self.get_const_decl(i)?;
+                let value = unsafe {
+                    z3_sys::Z3_model_get_const_interp(
+                        self.context.context,
+                        self.model,
+                        decl.func_decl,
+                    )
+                };
+                if value.is_null() {
+                    None
+                } else {
+                    Some((decl, Ast::new(self.context, value)))
+                }
+            })
+            .collect()
+    }
+
+    /// The value of every bitvector constant in this model, as the falcon
+    /// scalar `expression_to_ast` would translate to that constant. Only
+    /// constants the model interprets appear, so for values of every scalar
+    /// in a set of constraints use `il::solve_scalars`.
+    pub fn to_scalar_map(&self) -> HashMap<il::Scalar, il::Constant> {
+        self.consts()
+            .into_iter()
+            .filter_map(|(decl, value)| {
+                let bits = decl.range().bv_size()?;
+                let value = value.as_biguint()?;
+                Some((
+                    il::scalar(decl.name(), bits),
+                    il::Constant::new_big(value, bits),
+                ))
+            })
+            .collect()
+    }
+
+    /// Get the interpretation of an array-sorted term, or `None` if the model
+    /// does not give it as a finite set of entries over a default value.
+    pub fn get_array_interp(&self, array: &Ast<'c>) -> Option<ArrayInterp<'c>> {
+        let context = self.context;
+        let c = context.context;
+        let mut value = self.get_const_interp(array)?;
+
+        if unsafe { z3_sys::Z3_is_as_array(c, value.ast) } {
+            let decl = unsafe { z3_sys::Z3_get_as_array_func_decl(c, value.ast) };
+            let interp = self.get_func_interp(&FuncDecl::new(context, decl))?;
+            return Some(ArrayInterp {
+                entries: interp
+                    .entries
+                    .into_iter()
+                    .filter_map(|(mut args, value)| args.pop().map(|index| (index, value)))
+                    .collect(),
+                default: interp.else_value?,
+            });
+        }
+
+        // Otherwise the value is a chain of stores over a constant array.
+        let mut entries: Vec<(Ast<'c>, Ast<'c>)> = Vec::new();
+        loop {
+            if !unsafe { z3_sys::Z3_is_app(c, value.ast) } {
+                return None;
+            }
+            let app = unsafe { z3_sys::Z3_to_app(c, value.ast) };
+            let arg = |i| Ast::new(context, unsafe { z3_sys::Z3_get_app_arg(c, app, i) });
+            match unsafe { z3_sys::Z3_get_decl_kind(c, z3_sys::Z3_get_app_decl(c, app)) } {
+                z3_sys::DeclKind::STORE => {
+                    let index = arg(1);
+                    // Outer stores overwrite inner stores to the same index.
+                    if !entries
+                        .iter()
+                        .any(|entry| unsafe { z3_sys::Z3_is_eq_ast(c, entry.0.ast, index.ast) })
+                    {
+                        entries.push((index, arg(2)));
+                    }
+                    value = arg(0);
+                }
+                z3_sys::DeclKind::CONST_ARRAY => {
+                    return Some(ArrayInterp {
+                        entries,
+                        default: arg(0),
+                    });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Get the interpretation of a function, or `None` if the model does not
+    /// interpret it.
+    pub fn get_func_interp(&self, func_decl: &FuncDecl<'c>) -> Option<FuncInterp<'c>> {
+        let context = self.context;
+        let c = context.context;
+        unsafe {
+            let interp = z3_sys::Z3_model_get_func_interp(c, self.model, func_decl.func_decl);
+            if interp.is_null() {
+                return None;
+            }
+            z3_sys::Z3_func_interp_inc_ref(c, interp);
+            let mut entries = Vec::new();
+            for i in 0..z3_sys::Z3_func_interp_get_num_entries(c, interp) {
+                let entry = z3_sys::Z3_func_interp_get_entry(c, interp, i);
+                z3_sys::Z3_func_entry_inc_ref(c, entry);
+                let args = (0..z3_sys::Z3_func_entry_get_num_args(c, entry))
+                    .map(|j| Ast::new(context, z3_sys::Z3_func_entry_get_arg(c, entry, j)))
+                    .collect();
+                let value = Ast::new(context, z3_sys::Z3_func_entry_get_value(c, entry));
+                entries.push((args, value));
+                z3_sys::Z3_func_entry_dec_ref(c, entry);
+            }
+            let else_value = z3_sys::Z3_func_interp_get_else(c, interp);
+            let else_value = if else_value.is_null() {
+                None
+            } else {
+                Some(Ast::new(context, else_value))
+            };
+            z3_sys::Z3_func_interp_dec_ref(c, interp);
+            Some(FuncInterp {
+                entries,
+                else_value,
+            })
+        }
+    }
+
     fn inc_ref(&self) {
         unsafe {
             z3_sys::Z3_model_inc_ref(self.context.context, self.model);
@@ -70,3 +274,40 @@ impl<'c> Drop for Model<'c> {
         self.dec_ref();
     }
 }
+
+#[test]
+fn test_consts() -> ::error::Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new().enable_model());
+    let solver = Solver::new(&context);
+    let sort16 = context.mk_bv_sort(16);
+    let a = context.mk_var("a", &sort16)?;
+    let flag = context.mk_var("flag", &context.mk_bool_sort())?;
+    solver.assert(&context.eq(&a, &context.mk_numeral(0x1234, &sort16)?));
+    solver.assert(&flag);
+
+    let model = Model::new(&context, &solver).unwrap();
+    assert_eq!(model.num_consts(), 2);
+    assert!(model.get_const_decl(2).is_none());
+
+    let mut consts: Vec<(String, String)> = model
+        .consts()
+        .iter()
+        .map(|(decl, value)| (decl.name(), value.to_string().unwrap()))
+        .collect();
+    consts.sort();
+    assert_eq!(
+        consts,
+        vec![
+            ("a".to_string(), "#x1234".to_string()),
+            ("flag".to_string(), "true".to_string()),
+        ]
+    );
+
+    let scalars = model.to_scalar_map();
+    assert_eq!(scalars.len(), 1);
+    assert_eq!(scalars[&il::scalar("a", 16)], il::const_(0x1234, 16));
+
+    Ok(())
+}
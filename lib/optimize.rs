@@ -1,11 +1,44 @@
+use error::*;
+use std::cell::Cell;
+use std::ffi::{CStr, CString};
 use z3_sys;
 use Ast;
 use Check;
 use Context;
+use Params;
+use Statistics;
+
+/// A handle to an objective of an `Optimize`, for reading its bounds.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Objective {
+    index: u32,
+}
+
+impl Objective {
+    /// The position of this objective in `Optimize::get_objectives`.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+/// How an `Optimize` with several objectives trades them off.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Priority {
+    /// Optimize each objective in turn, in the order they were added, without
+    /// giving up on those before it.
+    Lexicographic,
+    /// Find optimums no objective can improve on without another getting
+    /// worse. Each check returns the next such optimum.
+    Pareto,
+    /// Optimize each objective independently of the others.
+    Box,
+}
 
 pub struct Optimize<'c> {
     pub(crate) optimize: z3_sys::Z3_optimize,
     context: &'c Context,
+    // Z3 does not check pops against pushes for an optimizer.
+    num_scopes: Cell<usize>,
 }
 
 impl<'c> Optimize<'c> {
@@ -14,34 +47,195 @@ impl<'c> Optimize<'c> {
         unsafe {
             z3_sys::Z3_optimize_inc_ref(context.context, optimize);
         }
-        Optimize { optimize, context }
+        Optimize {
+            optimize,
+            context,
+            num_scopes: Cell::new(0),
+        }
+    }
+
+    /// An optimizer holding the assertions and objectives of an SMT-LIB2
+    /// benchmark.
+    pub fn from_smt2_string(context: &'c Context, smt2: &str) -> Result<Optimize<'c>> {
+        let optimize = Optimize::new(context);
+        let smt2 = CString::new(smt2)?;
+        unsafe {
+            z3_sys::Z3_optimize_from_string(context.context, optimize.optimize, smt2.as_ptr());
+        }
+        context.check_error()?;
+        Ok(optimize)
+    }
+
+    pub fn assert(&self, term: &Ast<'c>) {
+        self.try_assert(term).unwrap()
     }
 
-    pub fn assert(&self, term: &Ast) {
+    /// Assert `term`, failing if it is not a Boolean term.
+    pub fn try_assert(&self, term: &Ast<'c>) -> Result<()> {
+        self.context.check_context(term)?;
         unsafe {
             z3_sys::Z3_optimize_assert(self.context.context, self.optimize, term.ast);
         }
+        self.context.check_error()
+    }
+
+    /// Assert `term` as a soft constraint, which the optimizer may violate
+    /// at a cost of `weight`. The optimizer minimizes the total cost of the
+    /// violated soft constraints in each `group`, which is an objective of
+    /// its own.
+    pub fn assert_soft(
+        &self,
+        term: &Ast<'c>,
+        weight: u64,
+        group: Option<&str>,
+    ) -> Result<Objective> {
+        self.context.check_context(term)?;
+        let weight = CString::new(weight.to_string())?;
+        let group = match group {
+            Some(group) => self.context.mk_symbol(group)?,
+            None => std::ptr::null_mut(),
+        };
+        let index = unsafe {
+            z3_sys::Z3_optimize_assert_soft(
+                self.context.context,
+                self.optimize,
+                term.ast,
+                weight.as_ptr(),
+                group,
+            )
+        };
+        self.context.check_error()?;
+        Ok(Objective { index })
     }
 
     pub fn check(&self) -> Check {
-        let lbool = unsafe {
+        Check::from_lbool(unsafe {
             z3_sys::Z3_optimize_check(self.context.context, self.optimize, 0, std::ptr::null())
+        })
+    }
+
+    pub fn maximize(&self, term: &Ast<'c>) -> Objective {
+        self.try_maximize(term).unwrap()
+    }
+
+    /// Maximize `term`, failing if it is neither a bitvector nor arithmetic.
+    pub fn try_maximize(&self, term: &Ast<'c>) -> Result<Objective> {
+        self.context.check_context(term)?;
+        let index =
+            unsafe { z3_sys::Z3_optimize_maximize(self.context.context, self.optimize, term.ast) };
+        self.context.check_error()?;
+        Ok(Objective { index })
+    }
+
+    pub fn minimize(&self, term: &Ast<'c>) -> Objective {
+        self.try_minimize(term).unwrap()
+    }
+
+    /// Minimize `term`, failing if it is neither a bitvector nor arithmetic.
+    pub fn try_minimize(&self, term: &Ast<'c>) -> Result<Objective> {
+        self.context.check_context(term)?;
+        let index =
+            unsafe { z3_sys::Z3_optimize_minimize(self.context.context, self.optimize, term.ast) };
+        self.context.check_error()?;
+        Ok(Objective { index })
+    }
+
+    /// The terms this optimizer maximizes or minimizes, in the order they
+    /// were added.
+    pub fn get_objectives(&self) -> Vec<Ast<'c>> {
+        Ast::from_ast_vector(self.context, unsafe {
+            z3_sys::Z3_optimize_get_objectives(self.context.context, self.optimize)
+        })
+    }
+
+    /// The lower bound found for `objective` by the last check.
+    pub fn get_lower(&self, objective: Objective) -> Option<Ast<'c>> {
+        let lower = unsafe {
+            z3_sys::Z3_optimize_get_lower(self.context.context, self.optimize, objective.index)
         };
-        match lbool {
-            z3_sys::Z3_L_FALSE => Check::Unsat,
-            z3_sys::Z3_L_TRUE => Check::Sat,
-            _ => Check::Unknown,
+        if self.context.check_error().is_err() || lower.is_null() {
+            None
+        } else {
+            Some(Ast::new(self.context, lower))
         }
     }
 
-    pub fn maximize(&self, term: &Ast) {
+    /// The upper bound found for `objective` by the last check.
+    pub fn get_upper(&self, objective: Objective) -> Option<Ast<'c>> {
+        let upper = unsafe {
+            z3_sys::Z3_optimize_get_upper(self.context.context, self.optimize, objective.index)
+        };
+        if self.context.check_error().is_err() || upper.is_null() {
+            None
+        } else {
+            Some(Ast::new(self.context, upper))
+        }
+    }
+
+    /// Create a backtracking point. Assertions and objectives added after
+    /// it are removed by the matching `pop`.
+    pub fn push(&self) {
+        unsafe { z3_sys::Z3_optimize_push(self.context.context, self.optimize) }
+        self.num_scopes.set(self.num_scopes.get() + 1);
+    }
+
+    /// Backtrack to the last backtracking point. Fails if there is none.
+    pub fn pop(&self) -> Result<()> {
+        if self.num_scopes.get() == 0 {
+            return Err(ErrorKind::Z3(
+                z3_sys::ErrorCode::IOB,
+                "no backtracking point to pop".to_string(),
+            )
+            .into());
+        }
+        unsafe { z3_sys::Z3_optimize_pop(self.context.context, self.optimize) }
+        self.num_scopes.set(self.num_scopes.get() - 1);
+        self.context.check_error()
+    }
+
+    /// Why the last check returned `Check::Unknown`, such as "timeout" or
+    /// "canceled".
+    pub fn reason_unknown(&self) -> Option<String> {
+        let s =
+            unsafe { z3_sys::Z3_optimize_get_reason_unknown(self.context.context, self.optimize) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            cs.to_str().ok().map(|s| s.to_string())
+        }
+    }
+
+    /// Configure this optimizer. Fails if `params` holds a parameter the
+    /// optimizer does not know.
+    pub fn set_params(&self, params: &Params<'c>) -> Result<()> {
+        self.context.check_context(params)?;
         unsafe {
-            z3_sys::Z3_optimize_maximize(self.context.context, self.optimize, term.ast);
+            z3_sys::Z3_optimize_set_params(self.context.context, self.optimize, params.params);
         }
+        self.context.check_error()
+    }
+
+    /// Set how this optimizer trades off several objectives. The default is
+    /// `Priority::Lexicographic`.
+    pub fn set_priority(&self, priority: Priority) -> Result<()> {
+        let params = Params::new(self.context);
+        params.set_symbol(
+            "priority",
+            match priority {
+                Priority::Lexicographic => "lex",
+                Priority::Pareto => "pareto",
+                Priority::Box => "box",
+            },
+        )?;
+        self.set_params(&params)
     }
 
-    pub fn minimize(&self, term: &Ast) {
-        unsafe { z3_sys::Z3_optimize_minimize(self.context.context, self.optimize, term.ast) };
+    /// Statistics about the work this optimizer has done.
+    pub fn statistics(&self) -> Statistics<'c> {
+        Statistics::new(self.context, unsafe {
+            z3_sys::Z3_optimize_get_statistics(self.context.context, self.optimize)
+        })
     }
 }
 
@@ -70,7 +264,7 @@ fn test_maximize() {
     let model = Model::new_optimize(&context, &optimize).unwrap();
     let a_value = model.get_const_interp(&a).unwrap();
 
-    let a_value_string = a_value.get_numeral_decimal_string(&context).unwrap();
+    let a_value_string = a_value.get_numeral_decimal_string().unwrap();
 
     println!("a_value_string {}", a_value_string);
 
@@ -78,6 +272,23 @@ fn test_maximize() {
 
     assert_eq!(a_value, 6);
 }
+
+#[test]
+fn test_assert_errors() -> Result<()> {
+    use Config;
+    use Sort;
+
+    let context = Context::new(Config::new());
+    let optimize = Optimize::new(&context);
+    let a = context.mk_var("a", &context.mk_bv_sort(8))?;
+    let array = context.mk_var("mem", &Sort::array(&a.sort(), &a.sort()))?;
+
+    assert!(optimize.try_assert(&a).is_err());
+    assert!(optimize.try_maximize(&array).is_err());
+    assert!(optimize.try_minimize(&array).is_err());
+
+    Ok(())
+}
 #[test]
 fn test_minimize() {
     use Config;
@@ -97,7 +308,7 @@ fn test_minimize() {
     let model = Model::new_optimize(&context, &optimize).unwrap();
     let a_value = model.get_const_interp(&a).unwrap();
 
-    let a_value_string = a_value.get_numeral_decimal_string(&context).unwrap();
+    let a_value_string = a_value.get_numeral_decimal_string().unwrap();
 
     println!("b_value_string {}", a_value_string);
 
@@ -105,3 +316,104 @@ fn test_minimize() {
 
     assert_eq!(a_value, 8);
 }
+
+#[test]
+fn test_from_smt2_string() -> Result<()> {
+    use Config;
+    use Model;
+
+    let context = Context::new(Config::new().enable_model());
+    let optimize = Optimize::from_smt2_string(
+        &context,
+        "(declare-const a (_ BitVec 32)) (assert (bvult a #x00000007)) (maximize a)",
+    )?;
+
+    let model = Model::new_optimize(&context, &optimize).unwrap();
+    let a = context.mk_var("a", &context.mk_bv_sort(32))?;
+    assert_eq!(model.get_const_interp(&a).unwrap().as_u64(), Some(6));
+
+    Ok(())
+}
+
+#[test]
+fn test_reason_unknown() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let optimize = Optimize::new(&context);
+
+    // A timeout of 1ms stops the optimizer long before it can factor this.
+    let params = Params::new(&context);
+    params.set_timeout(1);
+    optimize.set_params(&params)?;
+
+    let sort64 = context.mk_bv_sort(64);
+    let a = context.mk_var("a", &sort64)?;
+    let b = context.mk_var("b", &sort64)?;
+    optimize.assert(&context.eq(
+        &context.bvmul(&a, &b),
+        &context.mk_numeral(0xfffe_f3a3_c4e3_a9b5, &sort64)?,
+    ));
+    optimize.assert(&context.bvmul_no_overflow(&a, &b, false));
+    optimize.maximize(&a);
+
+    assert_eq!(optimize.check(), Check::Unknown);
+    let reason = optimize.reason_unknown().unwrap();
+    assert!(reason == "canceled" || reason == "timeout");
+    assert!(!optimize.statistics().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_objectives() -> Result<()> {
+    use Config;
+    use Model;
+
+    let context = Context::new(Config::new().enable_model());
+    let optimize = Optimize::new(&context);
+    optimize.set_priority(Priority::Box)?;
+
+    let sort8 = context.mk_bv_sort(8);
+    let a = context.mk_var("a", &sort8)?;
+    let b = context.mk_var("b", &sort8)?;
+    let sum = context.bvadd(&a, &b);
+    optimize.assert(&context.bvult(&sum, &context.mk_numeral(100, &sort8)?));
+    optimize.assert(&context.bvult(&a, &context.mk_numeral(50, &sort8)?));
+    optimize.assert(&context.bvult(&b, &context.mk_numeral(50, &sort8)?));
+    optimize.assert(&context.bvuge(&a, &context.mk_numeral(10, &sort8)?));
+
+    // Box priority bounds each objective on its own.
+    let a_max = optimize.maximize(&a);
+    let a_min = optimize.minimize(&a);
+    let b_max = optimize.maximize(&b);
+    assert_eq!(optimize.get_objectives().len(), 3);
+    assert_eq!(b_max.index(), 2);
+    assert_eq!(optimize.check(), Check::Sat);
+    assert_eq!(optimize.get_upper(a_max).unwrap().as_u64(), Some(49));
+    assert_eq!(optimize.get_lower(a_min).unwrap().as_u64(), Some(10));
+    assert_eq!(optimize.get_upper(b_max).unwrap().as_u64(), Some(49));
+
+    // Objectives added after a push are gone after the pop.
+    optimize.push();
+    optimize.maximize(&sum);
+    assert_eq!(optimize.get_objectives().len(), 4);
+    optimize.pop()?;
+    assert_eq!(optimize.get_objectives().len(), 3);
+    assert!(optimize.pop().is_err());
+
+    // Soft constraints give way by weight.
+    let optimize = Optimize::new(&context);
+    let x = context.mk_var("x", &sort8)?;
+    optimize.assert_soft(&context.eq(&x, &context.mk_numeral(1, &sort8)?), 1, None)?;
+    optimize.assert_soft(&context.eq(&x, &context.mk_numeral(2, &sort8)?), 5, None)?;
+    optimize.assert_soft(
+        &context.eq(&x, &context.mk_numeral(3, &sort8)?),
+        3,
+        Some("other"),
+    )?;
+    let model = Model::new_optimize(&context, &optimize).unwrap();
+    assert_eq!(model.get_const_interp(&x).unwrap().as_u64(), Some(2));
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use context::InContext;
+use error::*;
+use std::ffi::CStr;
+use z3_sys;
+use Context;
+
+/// A set of parameters for configuring a `Solver` or `Optimize`.
+pub struct Params<'c> {
+    pub(crate) params: z3_sys::Z3_params,
+    context: &'c Context,
+}
+
+impl<'c> InContext for Params<'c> {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        Some(self.context.context)
+    }
+}
+
+impl<'c> Params<'c> {
+    pub fn new(context: &'c Context) -> Params<'c> {
+        let params = unsafe { z3_sys::Z3_mk_params(context.context) };
+        unsafe {
+            z3_sys::Z3_params_inc_ref(context.context, params);
+        }
+        Params { params, context }
+    }
+
+    pub fn set_bool(&self, key: &str, value: bool) -> Result<()> {
+        let key = self.context.mk_symbol(key)?;
+        unsafe {
+            z3_sys::Z3_params_set_bool(self.context.context, self.params, key, value);
+        }
+        Ok(())
+    }
+
+    pub fn set_uint(&self, key: &str, value: u32) -> Result<()> {
+        let key = self.context.mk_symbol(key)?;
+        unsafe {
+            z3_sys::Z3_params_set_uint(self.context.context, self.params, key, value);
+        }
+        Ok(())
+    }
+
+    pub fn set_double(&self, key: &str, value: f64) -> Result<()> {
+        let key = self.context.mk_symbol(key)?;
+        unsafe {
+            z3_sys::Z3_params_set_double(self.context.context, self.params, key, value);
+        }
+        Ok(())
+    }
+
+    pub fn set_symbol(&self, key: &str, value: &str) -> Result<()> {
+        let key = self.context.mk_symbol(key)?;
+        let value = self.context.mk_symbol(value)?;
+        unsafe {
+            z3_sys::Z3_params_set_symbol(self.context.context, self.params, key, value);
+        }
+        Ok(())
+    }
+
+    /// Give up on a check after `milliseconds`, returning `Check::Unknown`.
+    pub fn set_timeout(&self, milliseconds: u32) {
+        self.set_uint("timeout", milliseconds).unwrap()
+    }
+
+    /// Give up on a check after `rlimit` units of work. Unlike a timeout,
+    /// this is deterministic across machines.
+    pub fn set_rlimit(&self, rlimit: u32) {
+        self.set_uint("rlimit", rlimit).unwrap()
+    }
+
+    pub fn set_random_seed(&self, random_seed: u32) {
+        self.set_uint("random_seed", random_seed).unwrap()
+    }
+
+    /// Give up on a check once it uses more than `megabytes` of memory.
+    pub fn set_max_memory(&self, megabytes: u32) {
+        self.set_uint("max_memory", megabytes).unwrap()
+    }
+
+    pub fn to_string(&self) -> Option<String> {
+        let s = unsafe { z3_sys::Z3_params_to_string(self.context.context, self.params) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            cs.to_str().ok().map(|s| s.to_string())
+        }
+    }
+}
+
+impl<'c> Drop for Params<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_params_dec_ref(self.context.context, self.params) }
+    }
+}
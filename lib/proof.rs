@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use z3_sys;
+use Ast;
+use DeclKind;
+use ProofRule;
+
+/// A single step of a proof: a conclusion which follows from the
+/// conclusions of its premises by `rule`.
+pub struct ProofStep<'c> {
+    rule: ProofRule,
+    premises: Vec<Ast<'c>>,
+    conclusion: Ast<'c>,
+    proof: Ast<'c>,
+}
+
+impl<'c> ProofStep<'c> {
+    /// The step a proof term proves, or `None` if `proof` is not a proof
+    /// term.
+    pub fn new(proof: &Ast<'c>) -> Option<ProofStep<'c>> {
+        let rule = match proof.decl_kind()? {
+            DeclKind::Proof(rule) => rule,
+            _ => return None,
+        };
+        // The conclusion is the last argument of a proof term, after the
+        // proofs of its premises.
+        let mut premises = proof.args();
+        let conclusion = premises.pop()?;
+        Some(ProofStep {
+            rule,
+            premises,
+            conclusion,
+            proof: proof.clone(),
+        })
+    }
+
+    pub fn rule(&self) -> ProofRule {
+        self.rule
+    }
+
+    /// The proof terms this step follows from.
+    pub fn premises(&self) -> &[Ast<'c>] {
+        &self.premises
+    }
+
+    /// The formula this step proves.
+    pub fn conclusion(&self) -> &Ast<'c> {
+        &self.conclusion
+    }
+
+    /// The proof term of this step.
+    pub fn proof(&self) -> &Ast<'c> {
+        &self.proof
+    }
+
+    /// Every step of `proof`, each after the steps of its premises, with
+    /// steps shared between premises given once. The last step proves the
+    /// conclusion of `proof`.
+    pub fn walk(proof: &Ast<'c>) -> Vec<ProofStep<'c>> {
+        let context = proof.context.context;
+        let id = |ast: &Ast| unsafe { z3_sys::Z3_get_ast_id(context, ast.ast) };
+
+        let mut steps = Vec::new();
+        let mut visited = HashSet::new();
+        // Walk the proof without recursion, as proofs can be very deep.
+        let mut stack: Vec<(ProofStep<'c>, usize)> = Vec::new();
+        if let Some(step) = ProofStep::new(proof) {
+            visited.insert(id(proof));
+            stack.push((step, 0));
+        }
+        while let Some((step, next)) = stack.pop() {
+            match step.premises.get(next).cloned() {
+                Some(premise) => {
+                    stack.push((step, next + 1));
+                    if visited.insert(id(&premise)) {
+                        if let Some(premise) = ProofStep::new(&premise) {
+                            stack.push((premise, 0));
+                        }
+                    }
+                }
+                None => steps.push(step),
+            }
+        }
+        steps
+    }
+}
+
+#[test]
+fn test_proof() -> ::error::Result<()> {
+    use Check;
+    use Config;
+    use Context;
+    use Solver;
+
+    let context = Context::new(Config::new().proof(true));
+    let solver = Solver::new(&context);
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let seven = context.mk_numeral(7, &sort32)?;
+    let a_lt_seven = context.bvult(&a, &seven);
+    solver.assert(&a_lt_seven);
+    solver.assert(&context.eq(&a, &context.bvadd(&seven, &seven)));
+
+    assert!(solver.proof().is_none());
+    assert_eq!(solver.check(), Check::Unsat);
+    let proof = solver.proof().unwrap();
+
+    let steps = ProofStep::walk(&proof);
+    let last = steps.last().unwrap();
+    assert_eq!(last.conclusion().decl_kind(), Some(DeclKind::False));
+    assert_eq!(last.proof().to_string(), proof.to_string());
+
+    // The proof rests on the assertion that a < 7.
+    assert!(steps.iter().any(|step| step.rule() == ProofRule::Asserted
+        && step.conclusion().to_string() == a_lt_seven.to_string()));
+
+    // Every premise is proved by an earlier step.
+    for (i, step) in steps.iter().enumerate() {
+        for premise in step.premises() {
+            assert!(steps[..i]
+                .iter()
+                .any(|earlier| earlier.proof().to_string() == premise.to_string()));
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,11 @@
+use error::*;
+use std::ffi::{CStr, CString};
 use z3_sys;
 use Ast;
 use Context;
+use Params;
+use Statistics;
+use Tactic;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Check {
@@ -9,6 +14,18 @@ pub enum Check {
     Unsat,
 }
 
+impl Check {
+    pub(crate) fn from_lbool(lbool: z3_sys::Z3_lbool) -> Check {
+        if lbool == z3_sys::Z3_L_FALSE {
+            Check::Unsat
+        } else if lbool == z3_sys::Z3_L_TRUE {
+            Check::Sat
+        } else {
+            Check::Unknown
+        }
+    }
+}
+
 pub struct Solver<'c> {
     pub(crate) solver: z3_sys::Z3_solver,
     context: &'c Context,
@@ -23,21 +40,213 @@ impl<'c> Solver<'c> {
         Solver { solver, context }
     }
 
-    pub fn assert(&self, constraint: &Ast) {
+    /// A solver holding the assertions of an SMT-LIB2 benchmark.
+    pub fn from_smt2_string(context: &'c Context, smt2: &str) -> Result<Solver<'c>> {
+        let solver = Solver::new(context);
+        let smt2 = CString::new(smt2)?;
+        unsafe {
+            z3_sys::Z3_solver_from_string(context.context, solver.solver, smt2.as_ptr());
+        }
+        context.check_error()?;
+        Ok(solver)
+    }
+
+    /// A solver which decides its constraints with `tactic`, rather than
+    /// the default strategy. The solver is not incremental, and returns
+    /// `Check::Unknown` if `tactic` neither proves nor refutes them.
+    pub fn from_tactic(tactic: &Tactic<'c>) -> Solver<'c> {
+        let context = tactic.context;
+        let solver = unsafe { z3_sys::Z3_mk_solver_from_tactic(context.context, tactic.tactic) };
+        unsafe {
+            z3_sys::Z3_solver_inc_ref(context.context, solver);
+        }
+        Solver { solver, context }
+    }
+
+    pub fn assert(&self, constraint: &Ast<'c>) {
+        self.try_assert(constraint).unwrap()
+    }
+
+    /// Assert `constraint`, failing if it is not a Boolean term.
+    pub fn try_assert(&self, constraint: &Ast<'c>) -> Result<()> {
+        self.context.check_context(constraint)?;
         unsafe {
             z3_sys::Z3_solver_assert(self.context.context, self.solver, constraint.ast);
         }
+        self.context.check_error()
+    }
+
+    /// Assert `constraint`, tracked by the Boolean constant `tracker`. If the
+    /// solver is unsat and `constraint` is part of the conflict, `tracker`
+    /// appears in `unsat_core`.
+    pub fn assert_and_track(&self, constraint: &Ast<'c>, tracker: &Ast<'c>) {
+        self.try_assert_and_track(constraint, tracker).unwrap()
+    }
+
+    /// Assert `constraint`, tracked by the Boolean constant `tracker`,
+    /// failing if either is not a Boolean term.
+    pub fn try_assert_and_track(&self, constraint: &Ast<'c>, tracker: &Ast<'c>) -> Result<()> {
+        self.context.check_context(constraint)?;
+        self.context.check_context(tracker)?;
+        unsafe {
+            z3_sys::Z3_solver_assert_and_track(
+                self.context.context,
+                self.solver,
+                constraint.ast,
+                tracker.ast,
+            );
+        }
+        self.context.check_error()
+    }
+
+    /// The constraints currently asserted in this solver.
+    pub fn assertions(&self) -> Vec<Ast<'c>> {
+        Ast::from_ast_vector(self.context, unsafe {
+            z3_sys::Z3_solver_get_assertions(self.context.context, self.solver)
+        })
     }
 
     pub fn check(&self) -> Check {
-        let lbool = unsafe { z3_sys::Z3_solver_check(self.context.context, self.solver) };
-        if lbool == z3_sys::Z3_L_FALSE {
-            Check::Unsat
-        } else if lbool == z3_sys::Z3_L_TRUE {
-            Check::Sat
+        Check::from_lbool(unsafe { z3_sys::Z3_solver_check(self.context.context, self.solver) })
+    }
+
+    /// Check the asserted constraints together with `assumptions`, which
+    /// hold for this check only. If the result is unsat, `unsat_core` gives
+    /// the conflicting subset of `assumptions`.
+    /// Panics if an assumption belongs to another context.
+    pub fn check_assumptions(&self, assumptions: &[&Ast<'c>]) -> Check {
+        self.context.check_contexts(assumptions).unwrap();
+        let assumptions: Vec<z3_sys::Z3_ast> = assumptions.iter().map(|a| a.ast).collect();
+        Check::from_lbool(unsafe {
+            z3_sys::Z3_solver_check_assumptions(
+                self.context.context,
+                self.solver,
+                assumptions.len() as u32,
+                assumptions.as_ptr(),
+            )
+        })
+    }
+
+    /// The number of backtracking points created by `push` and not yet
+    /// removed by `pop`.
+    pub fn num_scopes(&self) -> usize {
+        unsafe { z3_sys::Z3_solver_get_num_scopes(self.context.context, self.solver) as usize }
+    }
+
+    /// Remove the constraints asserted since the last `n` calls to `push`.
+    pub fn pop(&self, n: usize) -> Result<()> {
+        unsafe {
+            z3_sys::Z3_solver_pop(self.context.context, self.solver, n as u32);
+        }
+        self.context.check_error()
+    }
+
+    /// Configure this solver. Fails if `params` holds a parameter the solver
+    /// does not know.
+    pub fn set_params(&self, params: &Params<'c>) -> Result<()> {
+        self.context.check_context(params)?;
+        // Z3 only validates parameters itself once the solver has been used.
+        let c = self.context.context;
+        let valid = unsafe {
+            let param_descrs = z3_sys::Z3_solver_get_param_descrs(c, self.solver);
+            z3_sys::Z3_param_descrs_inc_ref(c, param_descrs);
+            z3_sys::Z3_params_validate(c, params.params, param_descrs);
+            // Read the error before the next call into Z3 clears it.
+            let valid = self.context.check_error();
+            z3_sys::Z3_param_descrs_dec_ref(c, param_descrs);
+            valid
+        };
+        valid?;
+        unsafe {
+            z3_sys::Z3_solver_set_params(self.context.context, self.solver, params.params);
+        }
+        self.context.check_error()
+    }
+
+    /// Create a backtracking point, which a later `pop` returns to.
+    pub fn push(&self) {
+        unsafe {
+            z3_sys::Z3_solver_push(self.context.context, self.solver);
+        }
+    }
+
+    /// The proof that the constraints are unsatisfiable, if the last check
+    /// returned unsat and the context was created with proofs enabled.
+    pub fn proof(&self) -> Option<Ast<'c>> {
+        let proof = unsafe { z3_sys::Z3_solver_get_proof(self.context.context, self.solver) };
+        if self.context.check_error().is_err() || proof.is_null() {
+            None
         } else {
-            Check::Unknown
+            Some(Ast::new(self.context, proof))
+        }
+    }
+
+    /// Why the last check returned `Check::Unknown`, such as "timeout" or
+    /// "canceled".
+    pub fn reason_unknown(&self) -> Option<String> {
+        let s = unsafe { z3_sys::Z3_solver_get_reason_unknown(self.context.context, self.solver) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            cs.to_str().ok().map(|s| s.to_string())
+        }
+    }
+
+    /// Remove all constraints and backtracking points from this solver.
+    pub fn reset(&self) {
+        unsafe {
+            z3_sys::Z3_solver_reset(self.context.context, self.solver);
+        }
+    }
+
+    /// Statistics about the work this solver has done.
+    pub fn statistics(&self) -> Statistics<'c> {
+        Statistics::new(self.context, unsafe {
+            z3_sys::Z3_solver_get_statistics(self.context.context, self.solver)
+        })
+    }
+
+    /// The assumptions and trackers responsible for the last check returning
+    /// unsat.
+    pub fn unsat_core(&self) -> Vec<Ast<'c>> {
+        Ast::from_ast_vector(self.context, unsafe {
+            z3_sys::Z3_solver_get_unsat_core(self.context.context, self.solver)
+        })
+    }
+
+    /// The constraints in this solver as a standalone SMT-LIB2 benchmark,
+    /// ending in `(check-sat)`.
+    pub fn to_smt2(&self) -> Result<String> {
+        let assertions: Vec<z3_sys::Z3_ast> = self
+            .assertions()
+            .iter()
+            .map(|assertion| assertion.ast)
+            .collect();
+        let empty = CString::new("").unwrap();
+        let status = CString::new("unknown").unwrap();
+        let formula = self.context.mk_true();
+        let s = unsafe {
+            z3_sys::Z3_benchmark_to_smtlib_string(
+                self.context.context,
+                empty.as_ptr(),
+                empty.as_ptr(),
+                status.as_ptr(),
+                empty.as_ptr(),
+                assertions.len() as u32,
+                assertions.as_ptr(),
+                formula.ast,
+            )
+        };
+        self.context.check_error()?;
+        if s.is_null() {
+            return Err(ErrorKind::Z3(
+                z3_sys::ErrorCode::Exception,
+                "Z3 returned no benchmark".to_string(),
+            )
+            .into());
         }
+        Ok(unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
     }
 }
 
@@ -46,3 +255,269 @@ impl<'c> Drop for Solver<'c> {
         unsafe { z3_sys::Z3_solver_dec_ref(self.context.context, self.solver) }
     }
 }
+
+#[test]
+fn test_push_pop() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let seven = context.mk_numeral(7, &sort32)?;
+
+    solver.assert(&context.bvult(&a, &seven));
+    assert_eq!(solver.num_scopes(), 0);
+
+    solver.push();
+    solver.assert(&context.eq(&a, &seven));
+    assert_eq!(solver.num_scopes(), 1);
+    assert_eq!(solver.assertions().len(), 2);
+    assert_eq!(solver.check(), Check::Unsat);
+
+    solver.pop(1)?;
+    assert_eq!(solver.num_scopes(), 0);
+    assert_eq!(solver.assertions().len(), 1);
+    assert_eq!(solver.check(), Check::Sat);
+
+    assert!(solver.pop(1).is_err());
+
+    solver.reset();
+    assert!(solver.assertions().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_assert_errors() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+    let a = context.mk_var("a", &context.mk_bv_sort(8))?;
+    let tracker = context.mk_bool_var("tracker")?;
+
+    // A bitvector is not a constraint.
+    assert!(solver.try_assert(&a).is_err());
+    assert!(solver.try_assert_and_track(&a, &tracker).is_err());
+    assert!(solver.try_assert_and_track(&tracker, &a).is_err());
+    assert!(solver.assertions().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_unsat_core() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let b = context.mk_var("b", &sort32)?;
+    let five = context.mk_numeral(5, &sort32)?;
+    let ten = context.mk_numeral(10, &sort32)?;
+
+    let branch0 = context.mk_bool_var("branch0")?;
+    let branch1 = context.mk_bool_var("branch1")?;
+    let branch2 = context.mk_bool_var("branch2")?;
+
+    solver.assert(&context.implies(&branch0, &context.bvult(&a, &five)));
+    solver.assert(&context.implies(&branch1, &context.bvult(&b, &five)));
+    solver.assert(&context.implies(&branch2, &context.bvugt(&a, &ten)));
+
+    assert_eq!(solver.check_assumptions(&[&branch0, &branch1]), Check::Sat);
+    assert_eq!(
+        solver.check_assumptions(&[&branch0, &branch1, &branch2]),
+        Check::Unsat
+    );
+
+    let core: Vec<String> = solver
+        .unsat_core()
+        .iter()
+        .map(|ast| ast.to_string().unwrap())
+        .collect();
+    assert!(core.contains(&"branch0".to_string()));
+    assert!(core.contains(&"branch2".to_string()));
+    assert!(!core.contains(&"branch1".to_string()));
+
+    let tracker = context.mk_bool_var("tracker")?;
+    solver.assert_and_track(&context.eq(&a, &ten), &tracker);
+    assert_eq!(solver.check_assumptions(&[&branch0]), Check::Unsat);
+    assert_eq!(solver.unsat_core().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_params() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    let params = Params::new(&context);
+    params.set_timeout(10_000);
+    params.set_rlimit(1_000_000);
+    params.set_random_seed(7);
+    params.set_max_memory(1024);
+    solver.set_params(&params)?;
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    solver.assert(&context.bvult(&a, &context.mk_numeral(7, &sort32)?));
+    assert_eq!(solver.check(), Check::Sat);
+
+    let params = Params::new(&context);
+    params.set_bool("not_a_parameter", true)?;
+    assert!(solver.set_params(&params).is_err());
+    assert!(Solver::new(&context).set_params(&params).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_interrupt() -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+
+    // Factoring a 128-bit semiprime will not finish before we interrupt it.
+    let sort = context.mk_bv_sort(128);
+    let x = context.mk_var("x", &sort)?;
+    let y = context.mk_var("y", &sort)?;
+    let one = context.mk_numeral(1, &sort)?;
+    let n = context.mk_numeral_str("340282366920938460843936948965011886881", &sort)?;
+    solver.assert(&context.eq(&context.bvmul(&x, &y), &n));
+    solver.assert(&context.bvmul_no_overflow(&x, &y, false));
+    solver.assert(&context.bvugt(&x, &one));
+    solver.assert(&context.bvugt(&y, &one));
+
+    let handle = context.interrupt_handle();
+    let done = AtomicBool::new(false);
+    let result = thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            }
+        });
+        let result = solver.check();
+        done.store(true, Ordering::SeqCst);
+        result
+    });
+
+    assert_eq!(result, Check::Unknown);
+    // Older releases of Z3 report "interrupted" rather than "canceled".
+    let reason = solver.reason_unknown().unwrap();
+    assert!(reason == "canceled" || reason == "interrupted");
+
+    Ok(())
+}
+
+#[test]
+fn test_from_tactic() -> Result<()> {
+    use Config;
+    use Model;
+
+    let context = Context::new(Config::new().enable_model());
+    let tactic = Tactic::new(&context, "simplify")?
+        .and_then(&Tactic::new(&context, "solve-eqs")?)?
+        .and_then(&Tactic::new(&context, "bit-blast")?)?
+        .and_then(&Tactic::new(&context, "sat")?)?;
+    let solver = Solver::from_tactic(&tactic);
+
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let b = context.mk_var("b", &sort32)?;
+    solver.assert(&context.eq(
+        &context.bvmul(&a, &context.mk_numeral(3, &sort32)?),
+        &context.mk_numeral(21, &sort32)?,
+    ));
+    solver.assert(&context.bvult(&a, &context.mk_numeral(100, &sort32)?));
+    solver.assert(&context.eq(&b, &context.bvadd(&a, &a)));
+    assert_eq!(solver.check(), Check::Sat);
+
+    let model = Model::new(&context, &solver).unwrap();
+    let a = model.get_const_interp(&a).unwrap();
+    assert_eq!(a.get_numeral_decimal_string().unwrap(), "7");
+    let b = model.get_const_interp(&b).unwrap();
+    assert_eq!(b.get_numeral_decimal_string().unwrap(), "14");
+
+    Ok(())
+}
+
+#[test]
+fn test_to_smt2() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    solver.assert(&context.bvugt(&a, &context.mk_numeral(7, &sort32)?));
+    solver.assert(&context.bvult(&a, &context.mk_numeral(9, &sort32)?));
+
+    let smt2 = solver.to_smt2()?;
+    assert!(smt2.contains("(declare-fun a () (_ BitVec 32))"));
+    assert!(smt2.contains("(check-sat)"));
+    assert!(!smt2.contains(":status )"));
+
+    Ok(())
+}
+
+#[test]
+fn test_from_smt2_string() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    solver.assert(&context.bvugt(&a, &context.mk_numeral(7, &sort32)?));
+    solver.assert(&context.bvult(&a, &context.mk_numeral(9, &sort32)?));
+
+    // A benchmark exported with to_smt2 loads back into an equivalent solver.
+    let replay = Solver::from_smt2_string(&context, &solver.to_smt2()?)?;
+    assert_eq!(replay.assertions().len(), 2);
+    assert_eq!(replay.check(), Check::Sat);
+
+    assert!(Solver::from_smt2_string(&context, "(assert (= b #x07))").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_statistics() -> Result<()> {
+    use Config;
+    use StatisticsValue;
+
+    let context = Context::new(Config::new());
+    let solver = Solver::new(&context);
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let b = context.mk_var("b", &sort32)?;
+    solver.assert(&context.eq(
+        &context.bvmul(&a, &b),
+        &context.mk_numeral(0x1234_5678, &sort32)?,
+    ));
+    solver.assert(&context.bvugt(&a, &context.mk_numeral(1, &sort32)?));
+    assert_eq!(solver.check(), Check::Sat);
+
+    let statistics = solver.statistics();
+    assert!(!statistics.is_empty());
+    assert_eq!(statistics.entries().len(), statistics.len());
+    match statistics.get("memory") {
+        Some(StatisticsValue::Double(memory)) => assert!(memory > 0.0),
+        memory => panic!("unexpected memory statistic {:?}", memory),
+    }
+    assert!(statistics.get("not a statistic").is_none());
+
+    Ok(())
+}
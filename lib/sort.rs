@@ -1,5 +1,84 @@
+use context::InContext;
+use error::*;
 use z3_sys;
+use Context;
 
-pub struct Sort {
+/// A reference-counted handle to a Z3 sort, bound to the lifetime of the
+/// `Context` that created it.
+pub struct Sort<'c> {
     pub(crate) sort: z3_sys::Z3_sort,
+    pub(crate) context: &'c Context,
+}
+
+impl<'c> InContext for Sort<'c> {
+    fn z3_context(&self) -> Option<z3_sys::Z3_context> {
+        Some(self.context.context)
+    }
+}
+
+impl<'c> Sort<'c> {
+    /// Wrap a raw `Z3_sort`, taking a reference to it.
+    pub(crate) fn new(context: &'c Context, sort: z3_sys::Z3_sort) -> Sort<'c> {
+        unsafe {
+            z3_sys::Z3_inc_ref(
+                context.context,
+                z3_sys::Z3_sort_to_ast(context.context, sort),
+            );
+        }
+        Sort { sort, context }
+    }
+
+    /// The sort of arrays indexed by `domain` holding values of `range`.
+    pub fn array(domain: &Sort<'c>, range: &Sort<'c>) -> Sort<'c> {
+        Sort::try_array(domain, range).unwrap()
+    }
+
+    /// The sort of arrays indexed by `domain` holding values of `range`.
+    pub fn try_array(domain: &Sort<'c>, range: &Sort<'c>) -> Result<Sort<'c>> {
+        domain.context.check_context(range)?;
+        Sort::try_new(domain.context, unsafe {
+            z3_sys::Z3_mk_array_sort(domain.context.context, domain.sort, range.sort)
+        })
+    }
+
+    pub fn is_bool(&self) -> bool {
+        unsafe {
+            z3_sys::Z3_get_sort_kind(self.context.context, self.sort) == z3_sys::SortKind::Bool
+        }
+    }
+
+    /// The number of bits in this sort, if it is a bitvector sort.
+    pub fn bv_size(&self) -> Option<usize> {
+        unsafe {
+            match z3_sys::Z3_get_sort_kind(self.context.context, self.sort) {
+                z3_sys::SortKind::BV => {
+                    Some(z3_sys::Z3_get_bv_sort_size(self.context.context, self.sort) as usize)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Wrap a raw `Z3_sort` returned by a call which may have failed.
+    pub(crate) fn try_new(context: &'c Context, sort: z3_sys::Z3_sort) -> Result<Sort<'c>> {
+        context.check_error()?;
+        Ok(Sort::new(context, sort))
+    }
+}
+
+impl<'c> Clone for Sort<'c> {
+    fn clone(&self) -> Sort<'c> {
+        Sort::new(self.context, self.sort)
+    }
+}
+
+impl<'c> Drop for Sort<'c> {
+    fn drop(&mut self) {
+        unsafe {
+            z3_sys::Z3_dec_ref(
+                self.context.context,
+                z3_sys::Z3_sort_to_ast(self.context.context, self.sort),
+            )
+        }
+    }
 }
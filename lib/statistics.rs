@@ -0,0 +1,78 @@
+use std::ffi::CStr;
+use z3_sys;
+use Context;
+
+/// The value of a single statistic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatisticsValue {
+    UInt(u32),
+    Double(f64),
+}
+
+/// Statistics about the work a `Solver` or `Optimize` has done, such as
+/// "conflicts", "decisions", "memory" and "time".
+pub struct Statistics<'c> {
+    stats: z3_sys::Z3_stats,
+    context: &'c Context,
+}
+
+impl<'c> Statistics<'c> {
+    /// Wrap a raw `Z3_stats`, taking a reference to it.
+    pub(crate) fn new(context: &'c Context, stats: z3_sys::Z3_stats) -> Statistics<'c> {
+        unsafe {
+            z3_sys::Z3_stats_inc_ref(context.context, stats);
+        }
+        Statistics { stats, context }
+    }
+
+    /// The number of statistics.
+    pub fn len(&self) -> usize {
+        unsafe { z3_sys::Z3_stats_size(self.context.context, self.stats) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every statistic, as `(key, value)` pairs.
+    pub fn entries(&self) -> Vec<(String, StatisticsValue)> {
+        let c = self.context.context;
+        (0..self.len() as u32)
+            .map(|i| unsafe {
+                let key = CStr::from_ptr(z3_sys::Z3_stats_get_key(c, self.stats, i))
+                    .to_string_lossy()
+                    .into_owned();
+                let value = if z3_sys::Z3_stats_is_uint(c, self.stats, i) {
+                    StatisticsValue::UInt(z3_sys::Z3_stats_get_uint_value(c, self.stats, i))
+                } else {
+                    StatisticsValue::Double(z3_sys::Z3_stats_get_double_value(c, self.stats, i))
+                };
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// The value of the statistic named `key`.
+    pub fn get(&self, key: &str) -> Option<StatisticsValue> {
+        self.entries()
+            .into_iter()
+            .find(|entry| entry.0 == key)
+            .map(|entry| entry.1)
+    }
+
+    pub fn to_string(&self) -> Option<String> {
+        let s = unsafe { z3_sys::Z3_stats_to_string(self.context.context, self.stats) };
+        if s.is_null() {
+            None
+        } else {
+            let cs = unsafe { CStr::from_ptr(s) };
+            cs.to_str().ok().map(|s| s.to_string())
+        }
+    }
+}
+
+impl<'c> Drop for Statistics<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_stats_dec_ref(self.context.context, self.stats) }
+    }
+}
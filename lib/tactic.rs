@@ -0,0 +1,316 @@
+use error::*;
+use std::ffi::CString;
+use z3_sys;
+use Context;
+use Goal;
+use Params;
+
+/// A Z3 tactic, which transforms a `Goal` into a set of subgoals.
+///
+/// Tactics are combined into pipelines, and a pipeline is run either with
+/// `Tactic::apply` or as a solver through `Solver::from_tactic`.
+pub struct Tactic<'c> {
+    pub(crate) tactic: z3_sys::Z3_tactic,
+    pub(crate) context: &'c Context,
+}
+
+impl<'c> Tactic<'c> {
+    /// The built-in tactic called `name`, such as "simplify" or "bit-blast".
+    pub fn new(context: &'c Context, name: &str) -> Result<Tactic<'c>> {
+        let name = CString::new(name)?;
+        let tactic = unsafe { z3_sys::Z3_mk_tactic(context.context, name.as_ptr()) };
+        Tactic::from_raw(context, tactic)
+    }
+
+    /// Wrap a raw `Z3_tactic`, taking a reference to it. Z3 returns a null
+    /// tactic when it rejects its arguments, such as an unknown parameter.
+    fn from_raw(context: &'c Context, tactic: z3_sys::Z3_tactic) -> Result<Tactic<'c>> {
+        context.check_error()?;
+        if tactic.is_null() {
+            return Err(ErrorKind::Z3(
+                z3_sys::ErrorCode::InvalidArg,
+                "Z3 returned a null tactic".to_string(),
+            )
+            .into());
+        }
+        unsafe {
+            z3_sys::Z3_tactic_inc_ref(context.context, tactic);
+        }
+        Ok(Tactic { tactic, context })
+    }
+
+    /// Apply this tactic, then `other` to every resulting subgoal.
+    pub fn and_then(&self, other: &Tactic<'c>) -> Result<Tactic<'c>> {
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_and_then(self.context.context, self.tactic, other.tactic)
+        })
+    }
+
+    /// Apply this tactic, and if it fails, apply `other` instead.
+    pub fn or_else(&self, other: &Tactic<'c>) -> Result<Tactic<'c>> {
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_or_else(self.context.context, self.tactic, other.tactic)
+        })
+    }
+
+    /// Apply `tactics` in parallel, keeping the first which succeeds. Fails
+    /// if `tactics` is empty.
+    pub fn par_or(context: &'c Context, tactics: &[&Tactic<'c>]) -> Result<Tactic<'c>> {
+        if tactics.is_empty() {
+            return Err(ErrorKind::Z3(
+                z3_sys::ErrorCode::InvalidArg,
+                "par_or needs at least one tactic".to_string(),
+            )
+            .into());
+        }
+        let tactics: Vec<z3_sys::Z3_tactic> = tactics.iter().map(|t| t.tactic).collect();
+        Tactic::from_raw(context, unsafe {
+            z3_sys::Z3_tactic_par_or(context.context, tactics.len() as u32, tactics.as_ptr())
+        })
+    }
+
+    /// Apply this tactic to a goal and its subgoals until it no longer
+    /// changes them, at most `max` times.
+    pub fn repeat(&self, max: u32) -> Result<Tactic<'c>> {
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_repeat(self.context.context, self.tactic, max)
+        })
+    }
+
+    /// Apply this tactic, failing if it takes longer than `milliseconds`.
+    pub fn try_for(&self, milliseconds: u32) -> Result<Tactic<'c>> {
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_try_for(self.context.context, self.tactic, milliseconds)
+        })
+    }
+
+    /// Apply `then` if `probe` holds for the goal, and `otherwise` if not.
+    pub fn cond(
+        probe: &Probe<'c>,
+        then: &Tactic<'c>,
+        otherwise: &Tactic<'c>,
+    ) -> Result<Tactic<'c>> {
+        Tactic::from_raw(probe.context, unsafe {
+            z3_sys::Z3_tactic_cond(
+                probe.context.context,
+                probe.probe,
+                then.tactic,
+                otherwise.tactic,
+            )
+        })
+    }
+
+    /// Apply this tactic if `probe` holds for the goal, and leave the goal
+    /// unchanged if not.
+    pub fn when(&self, probe: &Probe<'c>) -> Result<Tactic<'c>> {
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_when(self.context.context, probe.probe, self.tactic)
+        })
+    }
+
+    /// This tactic, configured with `params`.
+    pub fn with_params(&self, params: &Params<'c>) -> Result<Tactic<'c>> {
+        self.context.check_context(params)?;
+        Tactic::from_raw(self.context, unsafe {
+            z3_sys::Z3_tactic_using_params(self.context.context, self.tactic, params.params)
+        })
+    }
+
+    pub fn apply(&self, goal: &Goal<'c>) -> Result<ApplyResult<'c>> {
+        let apply_result =
+            unsafe { z3_sys::Z3_tactic_apply(self.context.context, self.tactic, goal.goal) };
+        self.context.check_error()?;
+        unsafe {
+            z3_sys::Z3_apply_result_inc_ref(self.context.context, apply_result);
+        }
+        Ok(ApplyResult {
+            apply_result,
+            context: self.context,
+        })
+    }
+}
+
+impl<'c> Drop for Tactic<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_tactic_dec_ref(self.context.context, self.tactic) }
+    }
+}
+
+/// A Z3 probe, which measures a property of a `Goal`. Probes decide which
+/// branch `Tactic::cond` and `Tactic::when` take.
+///
+/// Boolean probes return 1.0 for true and 0.0 for false.
+pub struct Probe<'c> {
+    probe: z3_sys::Z3_probe,
+    context: &'c Context,
+}
+
+impl<'c> Probe<'c> {
+    /// The built-in probe called `name`, such as "is-qfbv" or "size".
+    pub fn new(context: &'c Context, name: &str) -> Result<Probe<'c>> {
+        let name = CString::new(name)?;
+        let probe = unsafe { z3_sys::Z3_mk_probe(context.context, name.as_ptr()) };
+        Probe::from_raw(context, probe)
+    }
+
+    /// A probe which always returns `value`.
+    pub fn constant(context: &'c Context, value: f64) -> Result<Probe<'c>> {
+        Probe::from_raw(context, unsafe {
+            z3_sys::Z3_probe_const(context.context, value)
+        })
+    }
+
+    /// Wrap a raw `Z3_probe`, taking a reference to it.
+    fn from_raw(context: &'c Context, probe: z3_sys::Z3_probe) -> Result<Probe<'c>> {
+        context.check_error()?;
+        if probe.is_null() {
+            return Err(ErrorKind::Z3(
+                z3_sys::ErrorCode::InvalidArg,
+                "Z3 returned a null probe".to_string(),
+            )
+            .into());
+        }
+        unsafe {
+            z3_sys::Z3_probe_inc_ref(context.context, probe);
+        }
+        Ok(Probe { probe, context })
+    }
+
+    pub fn lt(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_lt(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn gt(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_gt(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn le(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_le(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn ge(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_ge(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn eq(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_eq(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn and(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_and(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn or(&self, other: &Probe<'c>) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_or(self.context.context, self.probe, other.probe)
+        })
+    }
+
+    pub fn not(&self) -> Result<Probe<'c>> {
+        Probe::from_raw(self.context, unsafe {
+            z3_sys::Z3_probe_not(self.context.context, self.probe)
+        })
+    }
+
+    pub fn apply(&self, goal: &Goal<'c>) -> f64 {
+        unsafe { z3_sys::Z3_probe_apply(self.context.context, self.probe, goal.goal) }
+    }
+}
+
+impl<'c> Drop for Probe<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_probe_dec_ref(self.context.context, self.probe) }
+    }
+}
+
+/// The subgoals produced by applying a `Tactic` to a `Goal`.
+pub struct ApplyResult<'c> {
+    apply_result: z3_sys::Z3_apply_result,
+    context: &'c Context,
+}
+
+impl<'c> ApplyResult<'c> {
+    pub fn subgoals(&self) -> Vec<Goal<'c>> {
+        let num_subgoals = unsafe {
+            z3_sys::Z3_apply_result_get_num_subgoals(self.context.context, self.apply_result)
+        };
+        (0..num_subgoals)
+            .map(|i| {
+                Goal::from_raw(self.context, unsafe {
+                    z3_sys::Z3_apply_result_get_subgoal(self.context.context, self.apply_result, i)
+                })
+            })
+            .collect()
+    }
+}
+
+impl<'c> Drop for ApplyResult<'c> {
+    fn drop(&mut self) {
+        unsafe { z3_sys::Z3_apply_result_dec_ref(self.context.context, self.apply_result) }
+    }
+}
+
+#[test]
+fn test_tactics() -> Result<()> {
+    use Config;
+
+    let context = Context::new(Config::new());
+    let sort32 = context.mk_bv_sort(32);
+    let a = context.mk_var("a", &sort32)?;
+    let b = context.mk_var("b", &sort32)?;
+
+    // a + 0 == b and b == 7 simplifies and solves to nothing.
+    let goal = Goal::new(&context);
+    goal.assert(&context.eq(&context.bvadd(&a, &context.mk_numeral(0, &sort32)?), &b));
+    goal.assert(&context.eq(&b, &context.mk_numeral(7, &sort32)?));
+    assert_eq!(goal.size(), 2);
+    assert!(goal.try_assert(&a).is_err());
+
+    let tactic =
+        Tactic::new(&context, "simplify")?.and_then(&Tactic::new(&context, "solve-eqs")?)?;
+    let subgoals = tactic.apply(&goal)?.subgoals();
+    assert_eq!(subgoals.len(), 1);
+    assert!(subgoals[0].is_decided_sat());
+
+    // A bitvector goal takes the first branch of a cond on is-qfbv.
+    let goal = Goal::new(&context);
+    goal.assert(&context.bvult(&a, &b));
+    let is_qfbv = Probe::new(&context, "is-qfbv")?;
+    assert_eq!(is_qfbv.apply(&goal), 1.0);
+    let tactic = Tactic::cond(
+        &is_qfbv,
+        &Tactic::new(&context, "skip")?,
+        &Tactic::new(&context, "fail")?,
+    )?;
+    assert!(tactic.apply(&goal).is_ok());
+    let tactic = Tactic::cond(
+        &is_qfbv.not()?,
+        &Tactic::new(&context, "skip")?,
+        &Tactic::new(&context, "fail")?,
+    )?;
+    assert!(tactic.apply(&goal).is_err());
+
+    assert!(Tactic::new(&context, "not-a-tactic").is_err());
+    assert!(Tactic::par_or(&context, &[]).is_err());
+
+    // An unknown parameter is an error rather than a null tactic.
+    let params = Params::new(&context);
+    params.set_bool("not_a_parameter", true)?;
+    assert!(Tactic::new(&context, "simplify")?
+        .with_params(&params)
+        .is_err());
+
+    Ok(())
+}